@@ -1,23 +1,40 @@
+#![allow(clippy::needless_return)]
+
 use chrono::{
     DateTime,
-    Local
+    Duration,
+    Local,
+    Months,
+    NaiveDate,
+    NaiveDateTime,
+    TimeZone
 };
 use serde::{
     Serialize,
     Deserialize
 };
 use std::{
+    cmp::Reverse,
+    collections::{
+        BTreeMap,
+        HashMap,
+        HashSet
+    },
+    fmt,
     fs::File,
     io::{
         self, 
+        BufRead,
         BufReader, 
+        IsTerminal,
         Write
     },
-    path::Path
+    path::Path,
+    str::FromStr
 };
 
 
-#[derive(PartialEq, PartialOrd, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 enum Priority
 {
     None,
@@ -28,268 +45,6100 @@ enum Priority
     VeryHigh,
 }
 
-impl Priority
+impl fmt::Display for Priority
 {
-    fn to_string(&self) -> String
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
     {
         match self 
         {
-            Priority::Low      => "Low".to_string(),
-            Priority::Medium   => "Medium".to_string(),
-            Priority::High     => "High".to_string(),
-            Priority::VeryHigh => "Very High".to_string(),
-            Priority::None     => "".to_string()
+            Priority::Low      => write!(f, "Low"),
+            Priority::Medium   => write!(f, "Medium"),
+            Priority::High     => write!(f, "High"),
+            Priority::VeryHigh => write!(f, "Very High"),
+            Priority::None     => write!(f, "")
         }
     }
 }
 
-#[derive(Serialize, Deserialize)]
+impl Priority
+{
+    /// ANSI escape code used to color this priority in the terminal
+    fn color_code(&self) -> &'static str
+    {
+        match self
+        {
+            Priority::Low      => "\x1b[32m",
+            Priority::Medium   => "\x1b[36m",
+            Priority::High     => "\x1b[33m",
+            Priority::VeryHigh => "\x1b[31m",
+            Priority::None     => ""
+        }
+    }
+}
+
+impl FromStr for Priority
+{
+    type Err = TaskError;
+
+    /// Accepts the menu index ("0".."4") or the priority name, ignoring case
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        match s.trim().to_lowercase().as_str()
+        {
+            "0" | "none"      => Ok(Priority::None),
+            "1" | "low"       => Ok(Priority::Low),
+            "2" | "medium"    => Ok(Priority::Medium),
+            "3" | "high"      => Ok(Priority::High),
+            "4" | "very high" => Ok(Priority::VeryHigh),
+            _                 => Err(TaskError::Parse(format!("Unknown priority \"{}\"", s.trim())))
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+enum Recurrence
+{
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl Recurrence
+{
+    /// The date one interval after `date`
+    fn advance(&self, date: DateTime<Local>) -> DateTime<Local>
+    {
+        match self
+        {
+            Recurrence::Daily   => date + Duration::days(1),
+            Recurrence::Weekly  => date + Duration::weeks(1),
+            Recurrence::Monthly => date.checked_add_months(Months::new(1)).unwrap_or(date + Duration::days(30))
+        }
+    }
+}
+
+impl fmt::Display for Recurrence
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self
+        {
+            Recurrence::Daily   => write!(f, "daily"),
+            Recurrence::Weekly  => write!(f, "weekly"),
+            Recurrence::Monthly => write!(f, "monthly")
+        }
+    }
+}
+
+/// Label shown as a colored dot in front of a task
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+enum TaskColor
+{
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+}
+
+impl TaskColor
+{
+    fn color_code(&self) -> &'static str
+    {
+        match self
+        {
+            TaskColor::Red     => "\x1b[31m",
+            TaskColor::Green   => "\x1b[32m",
+            TaskColor::Yellow  => "\x1b[33m",
+            TaskColor::Blue    => "\x1b[34m",
+            TaskColor::Magenta => "\x1b[35m",
+            TaskColor::Cyan    => "\x1b[36m"
+        }
+    }
+}
+
+impl fmt::Display for TaskColor
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self
+        {
+            TaskColor::Red     => write!(f, "red"),
+            TaskColor::Green   => write!(f, "green"),
+            TaskColor::Yellow  => write!(f, "yellow"),
+            TaskColor::Blue    => write!(f, "blue"),
+            TaskColor::Magenta => write!(f, "magenta"),
+            TaskColor::Cyan    => write!(f, "cyan")
+        }
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+enum SortKey
+{
+    Priority,
+    Name,
+    AddTime,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct SubTask
+{
+    title: String,
+    #[serde(default)]
+    done: bool
+}
+
+/// Everything but the name, priority and add time may be missing from a file,
+/// and fields this version doesn't know are skipped, so files from newer versions still load
+#[derive(Clone, Serialize, Deserialize)]
 struct Task
 {
+    /// Missing in version 1 files, ids are handed out on migration
+    #[serde(default)]
+    id: u64,
     name: String,
+    #[serde(default)]
     description: String,
     priority: Priority,
-    add_time: DateTime<Local>
+    add_time: DateTime<Local>,
+    /// Older files lack this, loading then sets it to `add_time`
+    #[serde(default)]
+    modified_time: DateTime<Local>,
+    #[serde(default)]
+    completed: bool,
+    #[serde(default)]
+    due_date: Option<DateTime<Local>>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    recurrence: Option<Recurrence>,
+    #[serde(default)]
+    progress: u8,
+    #[serde(default)]
+    subtasks: Vec<SubTask>,
+    #[serde(default)]
+    color: Option<TaskColor>,
+    /// Id of a task that has to be completed before this one
+    #[serde(default)]
+    blocked_by: Option<u64>,
+    /// Time tracked with the timer commands
+    #[serde(default = "Duration::zero", with = "duration_seconds")]
+    time_spent: Duration
 }
 
 impl Task
 {
-    fn new(name: String, description: String, priority: Priority)
-    -> Self
+    /// New tasks are made by `TaskManager::new_task`, which knows the current time
+    fn with_time(name: String, description: String, priority: Priority, add_time: DateTime<Local>) -> Self
     {
         return Self
         {
+            // Real id is assigned by TaskManager::push
+            id: 0,
             name,
             description,
             priority,
-            add_time: Local::now()
+            add_time,
+            modified_time: add_time,
+            completed: false,
+            due_date: None,
+            tags: Vec::new(),
+            recurrence: None,
+            progress: 0,
+            subtasks: Vec::new(),
+            color: None,
+            blocked_by: None,
+            time_spent: Duration::zero()
         };
     }
 
-    fn print(&self)
+    /// Splits comma separated input into tags, dropping empty entries
+    fn parse_tags(input: &str) -> Vec<String>
     {
-        println!("{} | {} | {}\n\"{}\"",
-            self.name,
-            self.priority.to_string(),
-            self.add_time.format("%d-%m-%Y  %H:%M:%S"),
-            self.description
-        );
+        return input
+            .split(',')
+            .map(|tag: &str| tag.trim().to_string())
+            .filter(|tag: &String| !tag.is_empty())
+            .collect();
+    }
+
+    /// Parses a progress percentage, rejecting anything outside 0..=100
+    fn parse_progress(input: &str) -> Result<u8, TaskError>
+    {
+        match input.trim().trim_end_matches('%').parse::<u8>()
+        {
+            Ok(progress) if progress <= 100 => Ok(progress),
+            _ => Err(TaskError::Parse(format!("Progress must be a number from 0 to 100, got \"{}\"", input.trim())))
+        }
     }
-}
 
+    /// Done and total subtask counts, `None` if the task has no subtasks
+    fn subtask_progress(&self) -> Option<(usize, usize)>
+    {
+        if self.subtasks.is_empty()
+        {
+            return None;
+        }
+        let done: usize = self.subtasks.iter().filter(|subtask: &&SubTask| subtask.done).count();
+        return Some((done, self.subtasks.len()));
+    }
 
-struct TaskManager
-{
-    tasks: Vec<Task>
-}
+    fn description_word_count(&self) -> usize
+    {
+        return self.description.split_whitespace().count();
+    }
 
-impl TaskManager
-{
-    fn new() -> Self
+    /// Forgets all work done on the task, for copies that start from scratch
+    fn start_over(&mut self)
     {
-        return Self { tasks: Vec::new() };
+        self.completed = false;
+        self.progress = 0;
+        self.time_spent = Duration::zero();
+        self.blocked_by = None;
+        for subtask in self.subtasks.iter_mut()
+        {
+            subtask.done = false;
+        }
     }
 
-    fn print(&self)
+    /// Whether the task is called `name`, ignoring case unless `match_case` is set
+    fn has_name(&self, name: &str, match_case: bool) -> bool
     {
-        for task in self.tasks.iter()
+        return match match_case
+        {
+            true  => self.name == name,
+            false => self.name.to_lowercase() == name.to_lowercase()
+        };
+    }
+
+    /// Marks the task as changed at `now`
+    fn touch(&mut self, now: DateTime<Local>)
+    {
+        self.modified_time = now;
+    }
+
+    /// Replaces the epoch default of a task read from an older file, as if unchanged since it was added
+    fn fill_modified_time(&mut self)
+    {
+        if self.modified_time < self.add_time
         {
-            task.print();
-            print!("\n");
+            self.modified_time = self.add_time;
         }
     }
 
-    fn sort(&mut self)
+    /// How long the task has existed at `now`
+    fn age(&self, now: DateTime<Local>) -> Duration
     {
-        // self.tasks.sort();
+        return now - self.add_time;
     }
 
-    fn push(&mut self, task: Task)
+    /// Rejects a due date before the task was added
+    fn validate(&self) -> Result<(), TaskError>
     {
-        self.tasks.push(task);    
+        // Due dates are entered to the minute, so the current minute still counts
+        match self.due_date
+        {
+            Some(due_date) if due_date + Duration::minutes(1) <= self.add_time => Err(TaskError::Invalid(
+                format!("Task {} cannot be due before it was added", self.name)
+            )),
+            _ => Ok(())
+        }
     }
 
-    fn pop(&mut self) -> Option<Task>
+    fn is_overdue(&self, now: DateTime<Local>) -> bool
     {
-        return self.tasks.pop();
+        return match self.due_date
+        {
+            Some(due_date) => due_date < now,
+            None           => false
+        };
     }
 
-    fn remove(&mut self, name: &str) -> Result<Task, String>
+    /// Detailed view, `now` decides whether the task is shown as overdue,
+    /// `blocked` whether it is shown as waiting for its blocker
+    fn to_display_string(&self, use_color: bool, now: DateTime<Local>, blocked: bool) -> String
     {
-        if let Some(index) = self.find(name)
+        let priority: String = if use_color && self.priority != Priority::None
         {
-            return Ok(self.tasks.remove(index))
+            format!("{}{}\x1b[0m", self.priority.color_code(), self.priority)
         }
         else
         {
-            return Err(format!("Task {} not found", name))   
+            self.priority.to_string()
+        };
+        let due: String = match self.due_date
+        {
+            Some(due_date) => format!(" | due {}{}",
+                due_date.format("%d-%m-%Y  %H:%M"),
+                if self.is_overdue(now) { " OVERDUE" } else { "" }
+            ),
+            None => String::new()
+        };
+        let tags: String = if self.tags.is_empty()
+        {
+            String::new()
         }
-    }
+        else
+        {
+            format!(" | tags: {}", self.tags.join(", "))
+        };
+        let recurrence: String = match &self.recurrence
+        {
+            Some(recurrence) => format!(" | repeats {}", recurrence),
+            None             => String::new()
+        };
 
-    fn find(&self, name: &str) -> Option<usize>
-    {
-        return self.tasks
-            .iter()
-            .position(|task: &Task| task.name.to_lowercase() == name.to_lowercase());
+        // Compare whole seconds, that's all the output shows
+        let modified: String = if (self.modified_time - self.add_time).num_seconds() > 0
+        {
+            format!(" | modified {}", self.modified_time.format("%d-%m-%Y  %H:%M:%S"))
+        }
+        else
+        {
+            String::new()
+        };
+        let subtasks: String = match self.subtask_progress()
+        {
+            Some((done, total)) => format!(" ({}/{})", done, total),
+            None                => String::new()
+        };
+        // Without color the dot can't tell labels apart, so name the color instead
+        let label: String = match (self.color, use_color)
+        {
+            (Some(color), true)  => format!("{}●\x1b[0m ", color.color_code()),
+            (Some(color), false) => format!("({}) ", color),
+            (None, _)            => String::new()
+        };
+        let spent: String = if self.time_spent.num_minutes() > 0
+        {
+            format!(" | spent {}", format_time_spent(self.time_spent))
+        }
+        else
+        {
+            String::new()
+        };
+        let lock: String = match self.blocked_by
+        {
+            Some(id) if blocked => format!(" | 🔒 blocked by #{}", id),
+            _                   => String::new()
+        };
+        // Dim and strike through finished tasks
+        let name: String = if use_color && self.completed
+        {
+            format!("\x1b[2;9m{}\x1b[0m", self.name)
+        }
+        else
+        {
+            self.name.clone()
+        };
+
+        return format!("{}#{} {} {}{} [{}%] | {} | {}{}{}{}{}{}{}\n\"{}\"",
+            label,
+            self.id,
+            if self.completed { "[x]" } else { "[ ]" },
+            name,
+            subtasks,
+            self.progress,
+            priority,
+            self.add_time.format("%d-%m-%Y  %H:%M:%S"),
+            modified,
+            due,
+            lock,
+            spent,
+            recurrence,
+            tags,
+            // Line up continuation lines with the text after the opening quote
+            self.description.replace('\n', "\n ")
+        );
     }
 
-    fn clear(&mut self)
+    fn print(&self, out: &mut dyn Write, use_color: bool, now: DateTime<Local>, blocked: bool) -> io::Result<()>
     {
-        self.tasks.clear();
+        return writeln!(out, "{}", self.to_display_string(use_color, now, blocked));
     }
 
-    fn store_to_file(&self, path: &str) -> Result<(), String>
+    /// Single line with marker, name cut to `COMPACT_NAME_WIDTH`, priority and due date
+    fn to_compact_string(&self) -> String
     {
-        if !Path::new(path).exists()
+        let name: String = if self.name.chars().count() > COMPACT_NAME_WIDTH
         {
-            let file: File = match File::create(path)
-            {
-                Ok(file) => file,
-                Err(e) => return Err(format!("Error to create file \"{}\": {}", path, e))
-            };
-
-            match serde_json::to_writer(&file, &self.tasks)
-            {
-                Ok(_) => {},
-                Err(_) => {}
-            }
+            format!("{}…", self.name.chars().take(COMPACT_NAME_WIDTH - 1).collect::<String>())
         }
-        Ok(())
+        else
+        {
+            self.name.clone()
+        };
+        let due: String = match self.due_date
+        {
+            Some(due_date) => format!(" | due {}", due_date.format("%d-%m-%Y  %H:%M")),
+            None           => String::new()
+        };
+        return format!("{} {:<width$} | {}{}",
+            if self.completed { "[x]" } else { "[ ]" },
+            name,
+            self.priority,
+            due,
+            width = COMPACT_NAME_WIDTH
+        );
     }
-    
-    fn read_from_file(&mut self, path: &str) -> Result<(), String>
+}
+
+
+/// Formats a duration as days and hours, e.g. "3d 4h"
+fn format_duration(duration: Duration) -> String
+{
+    let hours: i64 = duration.num_hours().max(0);
+    if hours < 24
     {
-        if Path::new(path).exists()
-        {
-            let file: File = match File::open(path)
-            {
-                Ok(file) => file,
-                Err(e) => return Err(format!("Error to open file: {}", e))
-            };
+        return format!("{}h", hours);
+    }
+    return format!("{}d {}h", hours / 24, hours % 24);
+}
+
+/// Formats a tracked time as hours and minutes, e.g. "1h 25m"
+fn format_time_spent(duration: Duration) -> String
+{
+    let minutes: i64 = duration.num_minutes().max(0);
+    return format!("{}h {}m", minutes / 60, minutes % 60);
+}
 
-            let reader: BufReader<File> = BufReader::new(file);
-            self.tasks = match serde_json::from_reader(reader)
+/// Last second of `date`, the due time used for whole days
+fn end_of_day(date: NaiveDate) -> Option<DateTime<Local>>
+{
+    return date.and_hms_opt(23, 59, 59).and_then(|date: NaiveDateTime| Local.from_local_datetime(&date).latest());
+}
+
+/// Reads a due date as "dd-mm-yyyy hh:mm", "today" or "tomorrow" (at 23:59:59),
+/// "+N" or "+Nd" for N days or "+Nh" for N hours after `now`
+fn parse_due(input: &str, now: DateTime<Local>) -> Result<DateTime<Local>, TaskError>
+{
+    let input: String = input.trim().to_lowercase();
+    let invalid = || TaskError::Parse(format!("Invalid date \"{}\"", input));
+
+    let due_date: Option<DateTime<Local>> = match input.as_str()
+    {
+        "today"    => end_of_day(now.date_naive()),
+        "tomorrow" => end_of_day(now.date_naive() + Duration::days(1)),
+        relative if relative.starts_with('+') => {
+            let amount: &str = &relative[1..];
+            let delay: Option<Duration> = match amount.strip_suffix('h')
             {
-                Ok(data) => data,
-                Err(e)       => return Err(format!("Error to read file: {}", e))
+                Some(hours) => hours.parse::<i64>().ok().and_then(Duration::try_hours),
+                None        => amount.strip_suffix('d').unwrap_or(amount).parse::<i64>().ok().and_then(Duration::try_days)
             };
-        }
-        Ok(())
+            delay.and_then(|delay: Duration| now.checked_add_signed(delay))
+        },
+        absolute => NaiveDateTime::parse_from_str(absolute, "%d-%m-%Y %H:%M")
+            .ok()
+            .and_then(|date: NaiveDateTime| Local.from_local_datetime(&date).single())
+    };
+    return due_date.ok_or_else(invalid);
+}
+
+/// Stores a `Duration` as whole seconds
+mod duration_seconds
+{
+    use chrono::Duration;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+    {
+        return serializer.serialize_i64(duration.num_seconds());
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error>
+    {
+        let seconds: i64 = i64::deserialize(deserializer)?;
+        return Ok(Duration::try_seconds(seconds).unwrap_or_else(Duration::zero));
     }
 }
 
+/// Path where the previous version of a saved file is kept
+fn backup_path(path: &str) -> String
+{
+    return format!("{}.bak", path);
+}
 
-struct ConsoleForTask
+/// Copies `path` to its backup path before it is saved over, if it exists
+fn back_up(path: &str) -> Result<(), TaskError>
 {
-    my_tasks: TaskManager
+    if !Path::new(path).exists()
+    {
+        return Ok(());
+    }
+    match std::fs::copy(path, backup_path(path))
+    {
+        Ok(_)  => Ok(()),
+        Err(e) => Err(TaskError::Io(format!("Error to back up file \"{}\": {}", path, e)))
+    }
 }
 
-impl ConsoleForTask
+/// Writes `content` to "<path>.tmp" and renames it over `path`,
+/// so a crash mid-write never leaves a truncated file behind
+fn write_atomically(path: &str, content: &[u8]) -> Result<(), TaskError>
 {
-    fn new() -> Self
+    let tmp_path: String = format!("{}.tmp", path);
+    let result: io::Result<()> = File::create(&tmp_path)
+        .and_then(|mut file: File| {
+            file.write_all(content)?;
+            file.sync_all()
+        })
+        .and_then(|_| std::fs::rename(&tmp_path, path));
+
+    match result
     {
-        Self {
-            my_tasks: TaskManager::new()
+        Ok(_)  => Ok(()),
+        Err(e) => {
+            let _ = std::fs::remove_file(&tmp_path);
+            Err(TaskError::Io(format!("Error to write file \"{}\": {}", path, e)))
         }
     }
+}
+
+/// Whether a path should be stored as YAML rather than JSON
+fn is_yaml_path(path: &str) -> bool
+{
+    return matches!(
+        Path::new(path).extension().and_then(|ext| ext.to_str()),
+        Some("yaml") | Some("yml")
+    );
+}
 
-    fn print_menu()
+/// Quotes a CSV field if it contains a separator, quote or line break
+fn csv_escape(field: &str) -> String
+{
+    if field.contains([',', '"', '\n', '\r'])
     {
-        println!("\nh - for help \n\n1. Add Task \n2. Pop Task \n3. Remove Task \n4. Find Task");
-        println!("5. List of Tasks \n6. Remove all Tasks \n7. Store Tasks to file \n8. Read Tasks from file \n9. Exit")
+        return format!("\"{}\"", field.replace('"', "\"\""));
     }
+    return field.to_string();
+}
 
-    fn input(query: &str) -> io::Result<String>
+/// Number of single character insertions, deletions and substitutions turning `a` into `b`
+fn levenshtein(a: &str, b: &str) -> usize
+{
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate()
     {
-        print!("{}", query);
-        io::stdout().flush()?;
-
-        let mut buffer: String = String::new();
-        io::stdin().read_line(&mut buffer)?;
-        
-        return Ok(buffer.to_string());
+        let mut current: Vec<usize> = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate()
+        {
+            let cost: usize = if a_char == *b_char { 0 } else { 1 };
+            current.push((previous[j] + cost).min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
     }
+    return previous[b.len()];
+}
 
-    fn process_input(&mut self) -> bool
+/// Splits CSV text into rows of fields, honouring quoted fields
+fn csv_parse(content: &str) -> Vec<Vec<String>>
+{
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    let mut row: Vec<String> = Vec::new();
+    let mut field: String = String::new();
+    let mut in_quotes: bool = false;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next()
     {
-        match Self::input("\nEnter command index: ")
+        if in_quotes
         {
-            Ok(command) =>
+            match c
             {
-                match command.trim()
-                {
-                    "h" => Self::print_menu(),
-                    "1" => {
-                        let name: String = Self::input("Enter name of new task: ").unwrap().trim().to_string();
-                        let description: String = Self::input("Enter description: ").unwrap().trim().to_string();
-                        let mut priority: Priority = Priority::None;
-                        while priority == Priority::None
-                        {
-                            priority = match Self::input("Enter index of priority (1. Low, 2. Medium, 3. High, 4. Very High): ")
-                                .unwrap()
-                                .trim()
-                            {
-                                "1" => Priority::Low,
-                                "2" => Priority::Medium,
-                                "3" => Priority::High,
-                                "4" => Priority::VeryHigh,
-                                _   => Priority::None,
-                            }
-                        }
-                        self.my_tasks.push(Task::new(name, description, priority));
-                    },
-                    "2" => {
-                        match self.my_tasks.pop()
-                        {
-                            Some(task) => println!("Task \"{}\" removed", task.name),
-                            None             => println!("List of tasks is empty"),
-                        }
-                    },
-                    "3" => {
-                        let name: String = Self::input("Enter name of task that you wanna remove: ").unwrap();
-                        println!("Task \"{}\" removed", self.my_tasks.remove(name.trim()).unwrap().name);
-
-                    },
-                    "4" => {
-                        let name: String = Self::input("Enter name of task that you wanna find: ").unwrap();
-                        match self.my_tasks.find(name.trim())
-                        {
-                            Some(index) => self.my_tasks.tasks[index].print(),
-                            None => println!("Task \"{}\" not found", name)
-                        }
-                    },
-                    "5" => {
-                        self.my_tasks.sort();
-                        self.my_tasks.print();
-                    },
-                    "6" => {
-                        self.my_tasks.clear();
-                        println!("All tasks removed");
-                    },
-                    "7" => {
-                        let path: String = Self::input("Enter path to file where to store tasks: ").unwrap();
-                        self.my_tasks.store_to_file(path.trim()).expect("Error to store to file");
-                    },
-                    "8" => {
-                        let path: String = Self::input("Enter path to file that store tasks: ").unwrap();
-                        self.my_tasks.read_from_file(path.trim()).expect("Error to read from file");
-                    }
-                    "9" => return false,
+                '"' if chars.peek() == Some(&'"') => { field.push('"'); chars.next(); },
+                '"' => in_quotes = false,
+                _   => field.push(c)
+            }
+            continue;
+        }
 
-                    _ => println!("Invalid input")
-                }
+        match c
+        {
+            '"'  => in_quotes = true,
+            ','  => row.push(std::mem::take(&mut field)),
+            '\r' => {},
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
             },
-            Err(e) => println!("Error user input: {e}")
-        };
-        return true;
+            _    => field.push(c)
+        }
+    }
+
+    if !field.is_empty() || !row.is_empty()
+    {
+        row.push(field);
+        rows.push(row);
     }
+    return rows;
 }
 
-fn main()
+#[derive(Debug, PartialEq)]
+enum TaskError
 {
-    let mut console: ConsoleForTask = ConsoleForTask::new();
-    println!("Task Manager 1.0");
-    ConsoleForTask::print_menu();
+    /// Holds the name (or "#id") of the task that was looked up
+    NotFound(String),
+    Io(String),
+    Parse(String),
+    /// Holds the name that is already taken
+    Duplicate(String),
+    /// The operation doesn't apply to the current state, e.g. moving the top task up
+    Invalid(String),
+    Empty,
+    /// Holds the task limit that would be exceeded
+    Full(usize),
+    /// Holds the name of the project that was looked up
+    NoProject(String)
+}
 
-    while console.process_input() {}
+impl fmt::Display for TaskError
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self
+        {
+            TaskError::NotFound(name)   => write!(f, "Task {} not found", name),
+            TaskError::Io(message)      => write!(f, "{}", message),
+            TaskError::Parse(message)   => write!(f, "{}", message),
+            TaskError::Duplicate(name)  => write!(f, "Task {} already exists", name),
+            TaskError::Invalid(message) => write!(f, "{}", message),
+            TaskError::Empty            => write!(f, "Task name cannot be empty"),
+            TaskError::Full(max)        => write!(f, "Task limit of {} reached, archive or clear tasks first", max),
+            TaskError::NoProject(name)  => write!(f, "Project {} not found", name)
+        }
+    }
+}
+
+impl std::error::Error for TaskError {}
+
+/// Summary counts over the whole task list
+struct TaskStats
+{
+    total: usize,
+    none: usize,
+    low: usize,
+    medium: usize,
+    high: usize,
+    very_high: usize,
+    completed: usize,
+    overdue: usize
+}
+
+/// File the console uses when no other path is given
+const DEFAULT_PATH: &str = "tasks.json";
+
+/// Environment variable that overrides `DEFAULT_PATH` and the configured path
+const FILE_VAR: &str = "TASKMANAGER_FILE";
+
+/// Name of the config file looked up in the home directory
+const CONFIG_FILE: &str = ".taskmanager.json";
+
+/// Archive file offered by the archive command
+const ARCHIVE_PATH: &str = "archive.json";
+
+/// Tasks shown per page by the paged listing
+const PER_PAGE: usize = 5;
+
+/// Names longer than this are cut off in the compact listing
+const COMPACT_NAME_WIDTH: usize = 30;
+
+/// Longest bar drawn by `TaskManager::priority_histogram`
+const HISTOGRAM_WIDTH: usize = 40;
+
+/// How many snapshots `TaskManager::undo` can go back
+const UNDO_LIMIT: usize = 10;
+
+/// User preferences read from `CONFIG_FILE` in the home directory
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+struct AppConfig
+{
+    default_path: String,
+    default_sort: SortKey,
+    use_color: bool,
+    pretty_json: bool,
+    max_tasks: Option<usize>,
+    clear_screen: bool
+}
+
+impl Default for AppConfig
+{
+    fn default() -> Self
+    {
+        return Self
+        {
+            default_path: DEFAULT_PATH.to_string(),
+            default_sort: SortKey::Priority,
+            use_color: true,
+            pretty_json: true,
+            max_tasks: None,
+            clear_screen: false
+        };
+    }
+}
+
+impl AppConfig
+{
+    /// Reads the config from `path`, falling back to defaults if it is missing or malformed
+    fn from_file(path: &Path) -> Self
+    {
+        let content: String = match std::fs::read_to_string(path)
+        {
+            Ok(content) => content,
+            Err(_) => return Self::default()
+        };
+
+        return match serde_json::from_str(&content)
+        {
+            Ok(config) => config,
+            Err(e) => {
+                println!("Ignoring config \"{}\": {}", path.display(), e);
+                Self::default()
+            }
+        };
+    }
+
+    fn load() -> Self
+    {
+        let mut config: Self = match std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"))
+        {
+            Some(home) => Self::from_file(&Path::new(&home).join(CONFIG_FILE)),
+            None       => Self::default()
+        };
+        if let Some(path) = env_path()
+        {
+            config.default_path = path;
+        }
+        return config;
+    }
+}
+
+/// Path set in `FILE_VAR`, if any
+fn env_path() -> Option<String>
+{
+    return std::env::var(FILE_VAR).ok().filter(|path: &String| !path.trim().is_empty());
+}
+
+/// Layout version written by `store_to_file` and `store_to_yaml`,
+/// version 3 added projects, which older versions would drop on saving
+const FILE_VERSION: u32 = 3;
+
+/// Project that holds the tasks of files written before projects existed
+const DEFAULT_PROJECT: &str = "default";
+
+fn default_project() -> String
+{
+    return DEFAULT_PROJECT.to_string();
+}
+
+/// Layout of the tasks file on disk
+#[derive(Serialize, Deserialize)]
+struct TaskFile<T, P = HashMap<String, Vec<Task>>>
+{
+    /// Missing in the first files with this layout, they are version 2 all the same
+    #[serde(default)]
+    version: u32,
+    /// `replace_tasks` never goes below the highest stored id, so this may be missing
+    #[serde(default)]
+    next_id: u64,
+    /// Name of the project whose tasks are in `tasks`
+    #[serde(default = "default_project")]
+    project: String,
+    /// Tasks of every other project
+    #[serde(default)]
+    projects: P,
+    tasks: T
+}
+
+/// Any layout a tasks file may have been written in
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredFile
+{
+    Versioned(TaskFile<Vec<Task>>),
+    /// Version 1, a bare array of tasks without ids
+    Legacy(Vec<Task>)
+}
+
+impl StoredFile
+{
+    /// Brings any supported layout up to the current one
+    fn migrate(self) -> Result<TaskFile<Vec<Task>>, TaskError>
+    {
+        match self
+        {
+            StoredFile::Versioned(data) if data.version > FILE_VERSION => Err(TaskError::Parse(format!(
+                "Error to read file: version {} is newer than supported version {}", data.version, FILE_VERSION
+            ))),
+            StoredFile::Versioned(data) => Ok(data),
+            StoredFile::Legacy(mut tasks) => {
+                for (index, task) in tasks.iter_mut().enumerate()
+                {
+                    task.id = index as u64 + 1;
+                }
+                Ok(TaskFile {
+                    version: FILE_VERSION,
+                    next_id: tasks.len() as u64 + 1,
+                    project: default_project(),
+                    projects: HashMap::new(),
+                    tasks
+                })
+            }
+        }
+    }
+}
+
+trait Clock
+{
+    fn now(&self) -> DateTime<Local>;
+}
+
+/// The real wall clock
+struct SystemClock;
+
+impl Clock for SystemClock
+{
+    fn now(&self) -> DateTime<Local>
+    {
+        return Local::now();
+    }
+}
+
+/// Clock stuck at one instant, for reproducible runs
+#[cfg(test)]
+struct FixedClock(DateTime<Local>);
+
+#[cfg(test)]
+impl Clock for FixedClock
+{
+    fn now(&self) -> DateTime<Local>
+    {
+        return self.0;
+    }
+}
+
+/// Named task lists, all but the active one which lives in `TaskManager::tasks`
+struct Projects
+{
+    active: String,
+    map: HashMap<String, Vec<Task>>
+}
+
+impl Projects
+{
+    fn new() -> Self
+    {
+        return Self { active: default_project(), map: HashMap::new() };
+    }
+
+    /// Every project name including the active one, sorted
+    fn names(&self) -> Vec<&str>
+    {
+        let mut names: Vec<&str> = self.map.keys().map(String::as_str).collect();
+        names.push(&self.active);
+        names.sort();
+        return names;
+    }
+
+    fn create(&mut self, name: &str) -> Result<(), TaskError>
+    {
+        if name.trim().is_empty()
+        {
+            return Err(TaskError::Invalid("Project name cannot be empty".to_string()));
+        }
+        if name == self.active || self.map.contains_key(name)
+        {
+            return Err(TaskError::Invalid(format!("Project {} already exists", name)));
+        }
+        self.map.insert(name.to_string(), Vec::new());
+        Ok(())
+    }
+
+    /// Moves the task called `name` from project `from` to project `to`, comparing names like
+    /// `TaskManager::match_case`, the active project must be put back into `map` by the caller first
+    fn move_task(&mut self, name: &str, from: &str, to: &str, match_case: bool) -> Result<(), TaskError>
+    {
+        for project in [from, to]
+        {
+            if !self.map.contains_key(project)
+            {
+                return Err(TaskError::NoProject(project.to_string()));
+            }
+        }
+        let source: &Vec<Task> = &self.map[from];
+        let index: usize = match source.iter().position(|task: &Task| task.has_name(name, match_case))
+        {
+            Some(index) => index,
+            None        => return Err(TaskError::NotFound(name.to_string()))
+        };
+        if from == to
+        {
+            return Ok(());
+        }
+        let moved: &str = &source[index].name;
+        if self.map[to].iter().any(|task: &Task| task.has_name(moved, match_case))
+        {
+            return Err(TaskError::Duplicate(moved.to_string()));
+        }
+
+        if let Some(source) = self.map.get_mut(from)
+        {
+            let task: Task = source.remove(index);
+            if let Some(target) = self.map.get_mut(to)
+            {
+                target.push(task);
+            }
+        }
+        Ok(())
+    }
+
+    /// Drops a project that isn't active and returns its tasks
+    fn delete(&mut self, name: &str) -> Result<Vec<Task>, TaskError>
+    {
+        if name == self.active
+        {
+            return Err(TaskError::Invalid(format!("Project {} is active, switch to another one first", name)));
+        }
+        match self.map.remove(name)
+        {
+            Some(tasks) => Ok(tasks),
+            None        => Err(TaskError::NoProject(name.to_string()))
+        }
+    }
+}
+
+struct TaskManager
+{
+    tasks: Vec<Task>,
+    /// Shared by all projects, so ids stay unique when tasks move between them
+    next_id: u64,
+    undo_stack: Vec<Vec<Task>>,
+    /// Compare task names case-sensitively in `find` and everything built on it
+    match_case: bool,
+    /// Most tasks the list may hold, `None` for no limit
+    max_tasks: Option<usize>,
+    projects: Projects,
+    /// Id of the task being timed and when its timer started
+    timer: Option<(u64, DateTime<Local>)>,
+    /// Source of the current time for everything that depends on it
+    clock: Box<dyn Clock>
+}
+
+impl TaskManager
+{
+    fn new() -> Self
+    {
+        return Self {
+            tasks: Vec::new(),
+            next_id: 1,
+            undo_stack: Vec::new(),
+            match_case: false,
+            max_tasks: None,
+            projects: Projects::new(),
+            timer: None,
+            clock: Box::new(SystemClock)
+        };
+    }
+
+    /// Task added now according to the manager's clock
+    fn new_task(&self, name: String, description: String, priority: Priority, due_date: Option<DateTime<Local>>) -> Task
+    {
+        let mut task: Task = Task::with_time(name, description, priority, self.clock.now());
+        task.due_date = due_date;
+        return task;
+    }
+
+    /// Moves a task between projects, either of which may be the active one
+    fn move_task(&mut self, name: &str, from: &str, to: &str) -> Result<(), TaskError>
+    {
+        let active: String = self.projects.active.clone();
+        self.projects.map.insert(active.clone(), std::mem::take(&mut self.tasks));
+        let result: Result<(), TaskError> = self.projects.move_task(name, from, to, self.match_case);
+        self.tasks = self.projects.map.remove(&active).unwrap_or_default();
+        if result.is_ok() && (from == active || to == active)
+        {
+            // An undo would bring back a list that doesn't match the other project anymore
+            self.undo_stack.clear();
+        }
+        return result;
+    }
+
+    /// Starts timing the task, stopping the timer of any other task first
+    fn start_timer(&mut self, name: &str) -> Result<Option<(String, Duration)>, TaskError>
+    {
+        let id: u64 = match self.find(name)
+        {
+            Some(index) => self.tasks[index].id,
+            None        => return Err(TaskError::NotFound(name.to_string()))
+        };
+        let stopped: Option<(String, Duration)> = self.stop_timer();
+        self.timer = Some((id, self.clock.now()));
+        Ok(stopped)
+    }
+
+    /// Adds the time since the timer started to its task, returns the task name and elapsed time
+    fn stop_timer(&mut self) -> Option<(String, Duration)>
+    {
+        let (id, started): (u64, DateTime<Local>) = self.timer.take()?;
+        let elapsed: Duration = self.clock.now() - started;
+        // The timed task may be in another project by now
+        let task: &mut Task = self.tasks
+            .iter_mut()
+            .chain(self.projects.map.values_mut().flatten())
+            .find(|task: &&mut Task| task.id == id)?;
+        task.time_spent += elapsed;
+        return Some((task.name.clone(), elapsed));
+    }
+
+    /// Makes project `name` the one all commands work on
+    fn switch_project(&mut self, name: &str) -> Result<(), TaskError>
+    {
+        if name == self.projects.active
+        {
+            return Ok(());
+        }
+        let tasks: Vec<Task> = match self.projects.map.remove(name)
+        {
+            Some(tasks) => tasks,
+            None        => return Err(TaskError::NoProject(name.to_string()))
+        };
+        let previous: String = std::mem::replace(&mut self.projects.active, name.to_string());
+        self.projects.map.insert(previous, std::mem::replace(&mut self.tasks, tasks));
+        // Snapshots belong to the list that was active when they were taken
+        self.undo_stack.clear();
+        Ok(())
+    }
+
+    /// Lists the tasks, leaving completed ones out unless `include_completed` is set
+    fn to_display_string(&self, use_color: bool, include_completed: bool) -> String
+    {
+        if self.tasks.is_empty()
+        {
+            return "No tasks yet.\n".to_string();
+        }
+
+        let now: DateTime<Local> = self.clock.now();
+        let shown: Vec<&Task> = if include_completed { self.tasks.iter().collect() } else { self.incomplete() };
+        let mut output: String = format!("Tasks ({}):\n", shown.len());
+        for task in shown.iter()
+        {
+            output.push_str(&task.to_display_string(use_color, now, self.blocker(task).is_some()));
+            output.push_str("\n\n");
+        }
+        let hidden: usize = self.tasks.len() - shown.len();
+        if hidden > 0
+        {
+            output.push_str(&format!("{} completed hidden\n", hidden));
+        }
+        return output;
+    }
+
+    fn print(&self, out: &mut dyn Write, use_color: bool, include_completed: bool) -> io::Result<()>
+    {
+        return write!(out, "{}", self.to_display_string(use_color, include_completed));
+    }
+
+    /// Like `print`, but one line per task
+    fn print_compact(&self, out: &mut dyn Write, include_completed: bool) -> io::Result<()>
+    {
+        if self.tasks.is_empty()
+        {
+            return writeln!(out, "No tasks yet.");
+        }
+
+        let shown: Vec<&Task> = if include_completed { self.tasks.iter().collect() } else { self.incomplete() };
+        for task in shown.iter()
+        {
+            writeln!(out, "{}", task.to_compact_string())?;
+        }
+        let hidden: usize = self.tasks.len() - shown.len();
+        if hidden > 0
+        {
+            writeln!(out, "{} completed hidden", hidden)?;
+        }
+        Ok(())
+    }
+
+    fn page_count(&self, per_page: usize) -> usize
+    {
+        return self.tasks.len().div_ceil(per_page.max(1)).max(1);
+    }
+
+    /// Tasks on the given 1-based page, out-of-range pages are clamped
+    fn page_slice(&self, page: usize, per_page: usize) -> &[Task]
+    {
+        let per_page: usize = per_page.max(1);
+        let page: usize = page.clamp(1, self.page_count(per_page));
+        let start: usize = (page - 1) * per_page;
+        let end: usize = (start + per_page).min(self.tasks.len());
+        return &self.tasks[start..end];
+    }
+
+    /// Prints one task as of the manager's clock, blocked while its blocker is incomplete
+    fn print_task(&self, out: &mut dyn Write, task: &Task, use_color: bool) -> io::Result<()>
+    {
+        return task.print(out, use_color, self.clock.now(), self.blocker(task).is_some());
+    }
+
+    fn print_page(&self, out: &mut dyn Write, page: usize, per_page: usize, use_color: bool) -> io::Result<()>
+    {
+        for task in self.page_slice(page, per_page)
+        {
+            self.print_task(out, task, use_color)?;
+            writeln!(out)?;
+        }
+        return writeln!(out, "Page {}/{}", page.clamp(1, self.page_count(per_page)), self.page_count(per_page));
+    }
+
+    fn sort(&mut self, key: SortKey)
+    {
+        match key
+        {
+            // Highest priority first, oldest first within the same priority
+            SortKey::Priority => self.tasks.sort_by(|a: &Task, b: &Task| {
+                b.priority
+                    .cmp(&a.priority)
+                    .then(a.add_time.cmp(&b.add_time))
+            }),
+            SortKey::Name     => self.tasks.sort_by_key(|task: &Task| task.name.to_lowercase()),
+            SortKey::AddTime  => self.tasks.sort_by_key(|task: &Task| task.add_time),
+        }
+    }
+
+    /// Checks that `count` more tasks fit under `max_tasks`
+    fn has_room(&self, count: usize) -> Result<(), TaskError>
+    {
+        match self.max_tasks
+        {
+            Some(max) if self.tasks.len() + count > max => Err(TaskError::Full(max)),
+            _ => Ok(())
+        }
+    }
+
+    fn push(&mut self, mut task: Task) -> Result<(), TaskError>
+    {
+        self.has_room(1)?;
+        task.id = self.next_id;
+        self.next_id += 1;
+        self.tasks.push(task);
+        Ok(())
+    }
+
+    /// Like `push`, but rejects a task whose name is empty or already taken
+    fn add(&mut self, task: Task) -> Result<(), TaskError>
+    {
+        if task.name.trim().is_empty()
+        {
+            return Err(TaskError::Empty);
+        }
+        if self.find(&task.name).is_some()
+        {
+            return Err(TaskError::Duplicate(task.name));
+        }
+        task.validate()?;
+        return self.push(task);
+    }
+
+    /// Remembers the current list so the next destructive operation can be undone
+    fn snapshot(&mut self)
+    {
+        if self.undo_stack.len() == UNDO_LIMIT
+        {
+            self.undo_stack.remove(0);
+        }
+        self.undo_stack.push(self.tasks.clone());
+    }
+
+    fn undo(&mut self) -> Result<(), TaskError>
+    {
+        match self.undo_stack.pop()
+        {
+            Some(tasks) => { self.tasks = tasks; Ok(()) },
+            None        => Err(TaskError::Invalid("Nothing to undo".to_string()))
+        }
+    }
+
+    fn pop(&mut self) -> Option<Task>
+    {
+        if !self.tasks.is_empty()
+        {
+            self.snapshot();
+        }
+        return self.tasks.pop();
+    }
+
+    /// Last task, which `pop` would remove next
+    fn peek(&self) -> Option<&Task>
+    {
+        return self.tasks.last();
+    }
+
+    fn remove(&mut self, name: &str) -> Result<Task, TaskError>
+    {
+        if let Some(index) = self.find(name)
+        {
+            self.snapshot();
+            return Ok(self.tasks.remove(index))
+        }
+        else
+        {
+            return Err(TaskError::NotFound(name.to_string()))   
+        }
+    }
+
+    /// Removes the task at `index` in list order
+    fn remove_at(&mut self, index: usize) -> Result<Task, TaskError>
+    {
+        if index >= self.tasks.len()
+        {
+            return Err(TaskError::Invalid(format!("No task number {}", index + 1)));
+        }
+        self.snapshot();
+        return Ok(self.tasks.remove(index));
+    }
+
+    fn name_matches(&self, task: &Task, name: &str) -> bool
+    {
+        return task.has_name(name, self.match_case);
+    }
+
+    fn find(&self, name: &str) -> Option<usize>
+    {
+        return self.tasks
+            .iter()
+            .position(|task: &Task| self.name_matches(task, name));
+    }
+
+    /// Indices of every task with the given name, for lists that contain duplicates
+    fn find_all(&self, name: &str) -> Vec<usize>
+    {
+        return self.tasks
+            .iter()
+            .enumerate()
+            .filter(|(_, task)| self.name_matches(task, name))
+            .map(|(index, _)| index)
+            .collect();
+    }
+
+    /// Every task index with its edit distance to `query`, closest first
+    fn find_fuzzy(&self, query: &str) -> Vec<(usize, usize)>
+    {
+        let query: String = if self.match_case { query.to_string() } else { query.to_lowercase() };
+        let mut matches: Vec<(usize, usize)> = self.tasks
+            .iter()
+            .enumerate()
+            .map(|(index, task)| {
+                let name: String = if self.match_case { task.name.clone() } else { task.name.to_lowercase() };
+                (index, levenshtein(&name, &query))
+            })
+            .collect();
+        matches.sort_by_key(|(_, distance): &(usize, usize)| *distance);
+        return matches;
+    }
+
+    fn move_up(&mut self, name: &str) -> Result<(), TaskError>
+    {
+        match self.find(name)
+        {
+            Some(0)     => Err(TaskError::Invalid(format!("Task {} is already at the top", name))),
+            Some(index) => { self.tasks.swap(index, index - 1); Ok(()) },
+            None        => Err(TaskError::NotFound(name.to_string()))
+        }
+    }
+
+    fn move_down(&mut self, name: &str) -> Result<(), TaskError>
+    {
+        match self.find(name)
+        {
+            Some(index) if index + 1 == self.tasks.len() => Err(TaskError::Invalid(format!("Task {} is already at the bottom", name))),
+            Some(index) => { self.tasks.swap(index, index + 1); Ok(()) },
+            None        => Err(TaskError::NotFound(name.to_string()))
+        }
+    }
+
+    fn swap(&mut self, a: &str, b: &str) -> Result<(), TaskError>
+    {
+        let first: usize = match self.find(a)
+        {
+            Some(index) => index,
+            None        => return Err(TaskError::NotFound(a.to_string()))
+        };
+        let second: usize = match self.find(b)
+        {
+            Some(index) => index,
+            None        => return Err(TaskError::NotFound(b.to_string()))
+        };
+
+        self.tasks.swap(first, second);
+        Ok(())
+    }
+
+    fn add_subtask(&mut self, name: &str, title: String) -> Result<(), TaskError>
+    {
+        match self.find(name)
+        {
+            Some(index) => {
+                self.tasks[index].subtasks.push(SubTask { title, done: false });
+                Ok(())
+            },
+            None => Err(TaskError::NotFound(name.to_string()))
+        }
+    }
+
+    /// Flips the done state of the subtask at the given 1-based position
+    fn toggle_subtask(&mut self, name: &str, number: usize) -> Result<(), TaskError>
+    {
+        let index: usize = match self.find(name)
+        {
+            Some(index) => index,
+            None        => return Err(TaskError::NotFound(name.to_string()))
+        };
+
+        let subtasks: &mut Vec<SubTask> = &mut self.tasks[index].subtasks;
+        match number.checked_sub(1).and_then(|i: usize| subtasks.get_mut(i))
+        {
+            Some(subtask) => { subtask.done = !subtask.done; Ok(()) },
+            None          => Err(TaskError::Invalid(format!("Task {} has no subtask {}", name, number)))
+        }
+    }
+
+    /// Checks that the task at `index` may be called `new`
+    fn check_rename(&self, index: usize, new: &str) -> Result<(), TaskError>
+    {
+        if new.trim().is_empty()
+        {
+            return Err(TaskError::Empty);
+        }
+        // Renaming a task onto itself is fine, e.g. to fix letter case
+        if let Some(other) = self.find(new)
+        {
+            if other != index
+            {
+                return Err(TaskError::Duplicate(new.to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    fn rename(&mut self, old: &str, new: &str) -> Result<(), TaskError>
+    {
+        let index: usize = match self.find(old)
+        {
+            Some(index) => index,
+            None        => return Err(TaskError::NotFound(old.to_string()))
+        };
+        self.check_rename(index, new)?;
+
+        self.tasks[index].name = new.to_string();
+        self.tasks[index].touch(self.clock.now());
+        Ok(())
+    }
+
+    fn set_priority(&mut self, name: &str, priority: Priority) -> Result<(), TaskError>
+    {
+        match self.find(name)
+        {
+            Some(index) => {
+                self.tasks[index].priority = priority;
+                self.tasks[index].touch(self.clock.now());
+                Ok(())
+            },
+            None => Err(TaskError::NotFound(name.to_string()))
+        }
+    }
+
+    /// Moves the due date `days` later, counting from now if the task has none
+    fn snooze(&mut self, name: &str, days: i64) -> Result<(), TaskError>
+    {
+        match self.find(name)
+        {
+            Some(index) => {
+                let task: &mut Task = &mut self.tasks[index];
+                let due_date: Option<DateTime<Local>> = Duration::try_days(days)
+                    .and_then(|delay: Duration| task.due_date.unwrap_or_else(|| self.clock.now()).checked_add_signed(delay));
+                match due_date
+                {
+                    Some(due_date) => {
+                        task.due_date = Some(due_date);
+                        task.touch(self.clock.now());
+                        Ok(())
+                    },
+                    None => Err(TaskError::Invalid(format!("Cannot snooze task {} by {} days", name, days)))
+                }
+            },
+            None => Err(TaskError::NotFound(name.to_string()))
+        }
+    }
+
+    /// Adds a fresh, incomplete copy of a task under a name that isn't taken yet
+    fn duplicate(&mut self, name: &str) -> Result<(), TaskError>
+    {
+        let index: usize = match self.find(name)
+        {
+            Some(index) => index,
+            None        => return Err(TaskError::NotFound(name.to_string()))
+        };
+
+        let mut copy: Task = self.tasks[index].clone();
+        copy.name = format!("{} (copy)", self.tasks[index].name);
+        let mut number: usize = 2;
+        while self.find(&copy.name).is_some()
+        {
+            copy.name = format!("{} (copy {})", self.tasks[index].name, number);
+            number += 1;
+        }
+        copy.add_time = self.clock.now();
+        copy.modified_time = copy.add_time;
+        copy.start_over();
+        return self.push(copy);
+    }
+
+    fn toggle_complete(&mut self, name: &str) -> Result<(), TaskError>
+    {
+        if let Some(index) = self.find(name)
+        {
+            if !self.tasks[index].completed
+            {
+                if let Some(blocker) = self.blocker(&self.tasks[index])
+                {
+                    return Err(TaskError::Invalid(format!("Task {} is blocked by task {}", name, blocker.name)));
+                }
+            }
+            self.tasks[index].completed = !self.tasks[index].completed;
+            self.tasks[index].touch(self.clock.now());
+
+            // Completing a recurring task schedules its next occurrence
+            let task: &Task = &self.tasks[index];
+            if let (true, Some(recurrence)) = (task.completed, &task.recurrence)
+            {
+                let mut next: Task = task.clone();
+                next.name = self.occurrence_name(&task.name);
+                next.start_over();
+                next.add_time = self.clock.now();
+                next.modified_time = next.add_time;
+                next.due_date = Some(recurrence.advance(task.due_date.unwrap_or(next.add_time)));
+                if let Err(e) = self.push(next)
+                {
+                    self.tasks[index].completed = false;
+                    return Err(e);
+                }
+            }
+            return Ok(())
+        }
+        else
+        {
+            return Err(TaskError::NotFound(name.to_string()))
+        }
+    }
+
+    /// Name for the next occurrence of a recurring task, numbered so it doesn't clash with earlier ones
+    fn occurrence_name(&self, name: &str) -> String
+    {
+        // "Water (2)" is followed by "Water (3)", not "Water (2) (2)"
+        let base: &str = match name.rsplit_once(" (")
+        {
+            Some((base, number)) if number.strip_suffix(')').is_some_and(|n: &str| n.parse::<usize>().is_ok()) => base,
+            _ => name
+        };
+        let mut number: usize = 2;
+        while self.find(&format!("{} ({})", base, number)).is_some()
+        {
+            number += 1;
+        }
+        return format!("{} ({})", base, number);
+    }
+
+    /// Incomplete task that keeps `task` from being completed
+    fn blocker(&self, task: &Task) -> Option<&Task>
+    {
+        return task.blocked_by
+            .and_then(|id: u64| self.find_by_id(id))
+            .map(|index: usize| &self.tasks[index])
+            .filter(|blocker: &&Task| !blocker.completed);
+    }
+
+    /// Makes the task wait for task `blocker_id`, or for nothing with `None`
+    fn set_blocker(&mut self, name: &str, blocker_id: Option<u64>) -> Result<(), TaskError>
+    {
+        let index: usize = match self.find(name)
+        {
+            Some(index) => index,
+            None        => return Err(TaskError::NotFound(name.to_string()))
+        };
+        if let Some(blocker_id) = blocker_id
+        {
+            // Follow the chain of blockers so two tasks can never wait for each other
+            let mut next: Option<u64> = Some(blocker_id);
+            let mut steps: usize = 0;
+            while let Some(id) = next
+            {
+                if id == self.tasks[index].id
+                {
+                    return Err(TaskError::Invalid(format!("Task {} cannot wait for itself", name)));
+                }
+                next = match self.find_by_id(id)
+                {
+                    Some(blocker) => self.tasks[blocker].blocked_by,
+                    None if steps == 0 => return Err(TaskError::NotFound(format!("#{}", id))),
+                    None => None
+                };
+                steps += 1;
+                if steps > self.tasks.len()
+                {
+                    break;
+                }
+            }
+        }
+        self.tasks[index].blocked_by = blocker_id;
+        self.tasks[index].touch(self.clock.now());
+        Ok(())
+    }
+
+    /// Marks the task done, doing nothing if it already is
+    fn complete(&mut self, name: &str) -> Result<(), TaskError>
+    {
+        match self.find(name)
+        {
+            Some(index) if self.tasks[index].completed => Ok(()),
+            Some(_) => self.toggle_complete(name),
+            None    => Err(TaskError::NotFound(name.to_string()))
+        }
+    }
+
+    /// Marks the task not done, doing nothing if it already is
+    fn uncomplete(&mut self, name: &str) -> Result<(), TaskError>
+    {
+        match self.find(name)
+        {
+            Some(index) => {
+                if self.tasks[index].completed
+                {
+                    self.tasks[index].completed = false;
+                    self.tasks[index].touch(self.clock.now());
+                }
+                Ok(())
+            },
+            None => Err(TaskError::NotFound(name.to_string()))
+        }
+    }
+
+    fn edit(&mut self, name: &str, new_name: Option<String>, new_desc: Option<String>, new_priority: Option<Priority>)
+    -> Result<(), TaskError>
+    {
+        let index: usize = match self.find(name)
+        {
+            Some(index) => index,
+            None        => return Err(TaskError::NotFound(name.to_string()))
+        };
+        if let Some(new_name) = &new_name
+        {
+            self.check_rename(index, new_name)?;
+        }
+
+        let task: &mut Task = &mut self.tasks[index];
+        if let Some(new_name) = new_name
+        {
+            task.name = new_name;
+        }
+        if let Some(new_desc) = new_desc
+        {
+            task.description = new_desc;
+        }
+        if let Some(new_priority) = new_priority
+        {
+            task.priority = new_priority;
+        }
+        task.touch(self.clock.now());
+        Ok(())
+    }
+
+    fn incomplete(&self) -> Vec<&Task>
+    {
+        return self.tasks.iter().filter(|task: &&Task| !task.completed).collect();
+    }
+
+    /// The `n` most recently added tasks, newest first
+    fn recent(&self, n: usize) -> Vec<&Task>
+    {
+        let mut tasks: Vec<&Task> = self.tasks.iter().collect();
+        tasks.sort_by_key(|task: &&Task| Reverse(task.add_time));
+        tasks.truncate(n);
+        return tasks;
+    }
+
+    /// Incomplete task that was added the longest time ago
+    fn oldest(&self) -> Option<&Task>
+    {
+        return self.tasks
+            .iter()
+            .filter(|task: &&Task| !task.completed)
+            .min_by_key(|task: &&Task| task.add_time);
+    }
+
+    /// How many tasks carry each tag, by lowercase tag name as `filter_by_tag` ignores case
+    fn tag_counts(&self) -> BTreeMap<String, usize>
+    {
+        let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+        for task in self.tasks.iter()
+        {
+            // A task tagged both "Work" and "work" still counts once
+            let tags: HashSet<String> = task.tags.iter().map(|tag: &String| tag.to_lowercase()).collect();
+            for tag in tags
+            {
+                *counts.entry(tag).or_insert(0) += 1;
+            }
+        }
+        return counts;
+    }
+
+    /// Incomplete tasks past their due date, most overdue first
+    fn overdue(&self) -> Vec<&Task>
+    {
+        let mut overdue: Vec<&Task> = self.tasks
+            .iter()
+            .filter(|task: &&Task| !task.completed && task.is_overdue(self.clock.now()))
+            .collect();
+        overdue.sort_by_key(|task: &&Task| task.due_date);
+        return overdue;
+    }
+
+    /// Most important incomplete task: highest priority, then nearest due date
+    fn suggest_next(&self) -> Option<&Task>
+    {
+        return self.tasks
+            .iter()
+            .filter(|task: &&Task| !task.completed)
+            .min_by_key(|task: &&Task| (Reverse(task.priority), task.due_date.is_none(), task.due_date));
+    }
+
+    /// Moves the due date of every overdue task to the end of today
+    fn reschedule_overdue(&mut self) -> usize
+    {
+        let now: DateTime<Local> = self.clock.now();
+        let today: DateTime<Local> = match end_of_day(now.date_naive())
+        {
+            // Past the end of the day the tasks would stay overdue
+            Some(today) if today > now => today,
+            _ => return 0
+        };
+        let mut count: usize = 0;
+        for task in self.tasks.iter_mut().filter(|task: &&mut Task| !task.completed && task.is_overdue(now))
+        {
+            task.due_date = Some(today);
+            task.touch(now);
+            count += 1;
+        }
+        return count;
+    }
+
+    /// Incomplete tasks due on the current calendar day, whatever the time
+    fn due_today(&self) -> Vec<&Task>
+    {
+        let today: NaiveDate = self.clock.now().date_naive();
+        return self.tasks
+            .iter()
+            .filter(|task: &&Task| !task.completed)
+            .filter(|task: &&Task| task.due_date.map(|due_date: DateTime<Local>| due_date.date_naive()) == Some(today))
+            .collect();
+    }
+
+    /// Tasks added between `from` and `to` inclusive, in either order
+    fn added_between(&self, from: DateTime<Local>, to: DateTime<Local>) -> Vec<&Task>
+    {
+        let (from, to): (DateTime<Local>, DateTime<Local>) = if from <= to { (from, to) } else { (to, from) };
+        return self.tasks
+            .iter()
+            .filter(|task: &&Task| from <= task.add_time && task.add_time <= to)
+            .collect();
+    }
+
+    /// Incomplete tasks due between now and `hours` from now
+    fn due_within(&self, hours: i64) -> Result<Vec<&Task>, TaskError>
+    {
+        let now: DateTime<Local> = self.clock.now();
+        let until: DateTime<Local> = match Duration::try_hours(hours).and_then(|window: Duration| now.checked_add_signed(window))
+        {
+            Some(until) => until,
+            None        => return Err(TaskError::Invalid(format!("Cannot look {} hours ahead", hours)))
+        };
+        return Ok(self.tasks
+            .iter()
+            .filter(|task: &&Task| !task.completed)
+            .filter(|task: &&Task| match task.due_date
+            {
+                Some(due_date) => now <= due_date && due_date <= until,
+                None           => false
+            })
+            .collect());
+    }
+
+    fn search(&self, query: &str) -> Vec<&Task>
+    {
+        let query: String = query.to_lowercase();
+        return self.tasks
+            .iter()
+            .filter(|task: &&Task| {
+                task.name.to_lowercase().contains(&query)
+                    || task.description.to_lowercase().contains(&query)
+            })
+            .collect();
+    }
+
+    fn filter_by_priority(&self, min: Priority) -> Vec<&Task>
+    {
+        return self.tasks
+            .iter()
+            .filter(|task: &&Task| task.priority >= min)
+            .collect();
+    }
+
+    fn filter_by_tag(&self, tag: &str) -> Vec<&Task>
+    {
+        let tag: String = tag.to_lowercase();
+        return self.tasks
+            .iter()
+            .filter(|task: &&Task| task.tags.iter().any(|t: &String| t.to_lowercase() == tag))
+            .collect();
+    }
+
+    /// Tasks bucketed by priority, highest first, leaving out empty buckets
+    fn grouped_by_priority(&self) -> Vec<(Priority, Vec<&Task>)>
+    {
+        let priorities: [Priority; 5] = [
+            Priority::VeryHigh,
+            Priority::High,
+            Priority::Medium,
+            Priority::Low,
+            Priority::None
+        ];
+
+        return priorities
+            .into_iter()
+            .map(|priority: Priority| {
+                let tasks: Vec<&Task> = self.tasks
+                    .iter()
+                    .filter(|task: &&Task| task.priority == priority)
+                    .collect();
+                (priority, tasks)
+            })
+            .filter(|(_, tasks)| !tasks.is_empty())
+            .collect();
+    }
+
+    fn find_by_id(&self, id: u64) -> Option<usize>
+    {
+        return self.tasks
+            .iter()
+            .position(|task: &Task| task.id == id);
+    }
+
+    fn remove_by_id(&mut self, id: u64) -> Result<Task, TaskError>
+    {
+        if let Some(index) = self.find_by_id(id)
+        {
+            self.snapshot();
+            return Ok(self.tasks.remove(index))
+        }
+        else
+        {
+            return Err(TaskError::NotFound(format!("#{}", id)))
+        }
+    }
+
+    fn total_words(&self) -> usize
+    {
+        return self.tasks.iter().map(Task::description_word_count).sum();
+    }
+
+    /// Mean progress across all tasks, 0 for an empty list
+    fn average_progress(&self) -> f64
+    {
+        if self.tasks.is_empty()
+        {
+            return 0.0;
+        }
+        let total: u64 = self.tasks.iter().map(|task: &Task| task.progress as u64).sum();
+        return total as f64 / self.tasks.len() as f64;
+    }
+
+    /// One bar per priority, the most common one is `HISTOGRAM_WIDTH` long
+    fn priority_histogram(&self) -> String
+    {
+        let stats: TaskStats = self.stats();
+        let counts: [(&str, usize); 5] = [
+            ("Very High", stats.very_high),
+            ("High", stats.high),
+            ("Medium", stats.medium),
+            ("Low", stats.low),
+            ("None", stats.none)
+        ];
+        let max: usize = counts.iter().map(|(_, count)| *count).max().unwrap_or(0).max(1);
+
+        let mut output: String = String::new();
+        for (label, count) in counts
+        {
+            // Round up so a single task still gets a visible bar
+            let width: usize = (count * HISTOGRAM_WIDTH).div_ceil(max);
+            output.push_str(&format!("{:<9} {} {}\n", label, "█".repeat(width), count));
+        }
+        return output;
+    }
+
+    fn stats(&self) -> TaskStats
+    {
+        let mut stats: TaskStats = TaskStats {
+            total: self.tasks.len(),
+            none: 0,
+            low: 0,
+            medium: 0,
+            high: 0,
+            very_high: 0,
+            completed: 0,
+            overdue: self.overdue().len()
+        };
+
+        for task in self.tasks.iter()
+        {
+            match task.priority
+            {
+                Priority::None     => stats.none += 1,
+                Priority::Low      => stats.low += 1,
+                Priority::Medium   => stats.medium += 1,
+                Priority::High     => stats.high += 1,
+                Priority::VeryHigh => stats.very_high += 1
+            }
+            if task.completed
+            {
+                stats.completed += 1;
+            }
+        }
+        return stats;
+    }
+
+    fn remove_completed(&mut self) -> usize
+    {
+        let count: usize = self.tasks.iter().filter(|task: &&Task| task.completed).count();
+        if count > 0
+        {
+            self.snapshot();
+            self.tasks.retain(|task: &Task| !task.completed);
+        }
+        return count;
+    }
+
+    /// Removes completed tasks last modified more than `days` ago, i.e. completed before then
+    fn purge_completed_older_than(&mut self, days: i64) -> usize
+    {
+        let cutoff: DateTime<Local> = match Duration::try_days(days).and_then(|age: Duration| self.clock.now().checked_sub_signed(age))
+        {
+            Some(cutoff) => cutoff,
+            None         => return 0
+        };
+        let is_old = |task: &Task| task.completed && task.modified_time < cutoff;
+        let count: usize = self.tasks.iter().filter(|task: &&Task| is_old(task)).count();
+        if count > 0
+        {
+            self.snapshot();
+            self.tasks.retain(|task: &Task| !is_old(task));
+        }
+        return count;
+    }
+
+    /// Removes tasks whose name, ignoring case, repeats an earlier one
+    fn dedupe(&mut self) -> usize
+    {
+        let mut seen: HashSet<String> = HashSet::new();
+        let keep: Vec<bool> = self.tasks
+            .iter()
+            .map(|task: &Task| seen.insert(task.name.to_lowercase()))
+            .collect();
+        let count: usize = keep.iter().filter(|keep: &&bool| !**keep).count();
+        if count > 0
+        {
+            self.snapshot();
+            let mut keep: std::vec::IntoIter<bool> = keep.into_iter();
+            self.tasks.retain(|_: &Task| keep.next().unwrap_or(true));
+        }
+        return count;
+    }
+
+    /// Moves completed tasks into the archive file at `archive_path`, keeping what it already holds
+    fn archive_completed(&mut self, archive_path: &str) -> Result<usize, TaskError>
+    {
+        let mut archive: TaskManager = TaskManager::new();
+        if Path::new(archive_path).exists()
+        {
+            archive.read_from_file(archive_path)?;
+        }
+
+        let count: usize = self.tasks.iter().filter(|task: &&Task| task.completed).count();
+        archive.tasks.extend(self.tasks.iter().filter(|task: &&Task| task.completed).cloned());
+        archive.next_id = archive.next_id.max(self.next_id);
+        archive.store_to_path(archive_path, true)?;
+
+        // Only drop tasks once the archive is safely written
+        self.tasks.retain(|task: &Task| !task.completed);
+        Ok(count)
+    }
+
+    fn clear(&mut self)
+    {
+        self.snapshot();
+        self.tasks.clear();
+    }
+
+    /// Writes tasks as JSON, `pretty` indents it for reading and diffing by hand
+    fn store_to_file(&self, path: &str, pretty: bool) -> Result<(), TaskError>
+    {
+        let data: TaskFile<&Vec<Task>, &HashMap<String, Vec<Task>>> = self.to_file();
+        let result: serde_json::Result<Vec<u8>> = if pretty
+        {
+            serde_json::to_vec_pretty(&data)
+        }
+        else
+        {
+            serde_json::to_vec(&data)
+        };
+        let content: Vec<u8> = match result
+        {
+            Ok(content) => content,
+            Err(e)      => return Err(TaskError::Io(format!("Error to write file \"{}\": {}", path, e)))
+        };
+        back_up(path)?;
+        return write_atomically(path, &content);
+    }
+    
+    fn export_csv(&self, path: &str) -> Result<(), TaskError>
+    {
+        let mut file: File = match File::create(path)
+        {
+            Ok(file) => file,
+            Err(e) => return Err(TaskError::Io(format!("Error to create file \"{}\": {}", path, e)))
+        };
+
+        let mut csv: String = String::from("name,description,priority,add_time,completed\n");
+        for task in self.tasks.iter()
+        {
+            csv.push_str(&format!("{},{},{},{},{}\n",
+                csv_escape(&task.name),
+                csv_escape(&task.description),
+                csv_escape(&task.priority.to_string()),
+                task.add_time.to_rfc3339(),
+                task.completed
+            ));
+        }
+
+        match file.write_all(csv.as_bytes())
+        {
+            Ok(_)  => Ok(()),
+            Err(e) => Err(TaskError::Io(format!("Error to write file \"{}\": {}", path, e)))
+        }
+    }
+
+    fn import_csv(&mut self, path: &str) -> Result<usize, TaskError>
+    {
+        let content: String = match std::fs::read_to_string(path)
+        {
+            Ok(content) => content,
+            Err(e) => return Err(TaskError::Io(format!("Error to read file \"{}\": {}", path, e)))
+        };
+
+        // Parse everything first so a broken row doesn't leave a half-imported list
+        let mut imported: Vec<Task> = Vec::new();
+        for (line, row) in csv_parse(&content).into_iter().enumerate().skip(1)
+        {
+            if row.len() != 5
+            {
+                return Err(TaskError::Parse(format!("Error to read CSV row {}: expected 5 fields, found {}", line + 1, row.len())));
+            }
+
+            let priority: Priority = row[2].parse().unwrap_or(Priority::None);
+            let add_time: DateTime<Local> = match DateTime::parse_from_rfc3339(&row[3])
+            {
+                Ok(time) => time.with_timezone(&Local),
+                Err(e) => return Err(TaskError::Parse(format!("Error to read CSV row {}: {}", line + 1, e)))
+            };
+
+            let mut task: Task = Task::with_time(row[0].clone(), row[1].clone(), priority, add_time);
+            task.completed = row[4] == "true";
+            imported.push(task);
+        }
+
+        let count: usize = imported.len();
+        self.has_room(count)?;
+        for task in imported
+        {
+            self.push(task)?;
+        }
+        Ok(count)
+    }
+
+    /// Writes tasks as a Markdown checklist with the description indented under each item
+    fn export_markdown(&self, path: &str) -> Result<(), TaskError>
+    {
+        let mut markdown: String = String::new();
+        for task in self.tasks.iter()
+        {
+            markdown.push_str(&format!("- [{}] {}", if task.completed { "x" } else { " " }, task.name));
+            if task.priority != Priority::None
+            {
+                markdown.push_str(&format!(" **{}**", task.priority));
+            }
+            markdown.push('\n');
+            for line in task.description.lines().filter(|line: &&str| !line.trim().is_empty())
+            {
+                markdown.push_str(&format!("  {}\n", line));
+            }
+        }
+        return write_atomically(path, markdown.as_bytes());
+    }
+
+    /// Writes the single task called `name` as JSON
+    fn export_task(&self, name: &str, path: &str) -> Result<(), TaskError>
+    {
+        let task: &Task = match self.find(name)
+        {
+            Some(index) => &self.tasks[index],
+            None        => return Err(TaskError::NotFound(name.to_string()))
+        };
+        match serde_json::to_vec_pretty(task)
+        {
+            Ok(content) => write_atomically(path, &content),
+            Err(e)      => Err(TaskError::Io(format!("Error to write file \"{}\": {}", path, e)))
+        }
+    }
+
+    /// Appends a task written by `export_task`, returns its name
+    fn import_task(&mut self, path: &str) -> Result<String, TaskError>
+    {
+        let content: String = match std::fs::read_to_string(path)
+        {
+            Ok(content) => content,
+            Err(e) => return Err(TaskError::Io(format!("Error to read file \"{}\": {}", path, e)))
+        };
+        let mut task: Task = match serde_json::from_str(&content)
+        {
+            Ok(task) => task,
+            Err(e) => return Err(TaskError::Parse(format!("Error to read file \"{}\": {}", path, e)))
+        };
+        // The blocker id belongs to the list the task came from
+        task.blocked_by = None;
+        task.fill_modified_time();
+        let name: String = task.name.clone();
+        self.add(task)?;
+        Ok(name)
+    }
+
+    /// Writes one JSON object per task and line
+    fn export_jsonl(&self, path: &str) -> Result<(), TaskError>
+    {
+        let mut content: Vec<u8> = Vec::new();
+        for task in self.tasks.iter()
+        {
+            if let Err(e) = serde_json::to_writer(&mut content, task)
+            {
+                return Err(TaskError::Io(format!("Error to write file \"{}\": {}", path, e)));
+            }
+            content.push(b'\n');
+        }
+        return write_atomically(path, &content);
+    }
+
+    /// Imports one task per line, returns how many were imported and how many malformed lines were skipped
+    fn import_jsonl(&mut self, path: &str) -> Result<(usize, usize), TaskError>
+    {
+        let file: File = match File::open(path)
+        {
+            Ok(file) => file,
+            Err(e) => return Err(TaskError::Io(format!("Error to read file \"{}\": {}", path, e)))
+        };
+
+        let mut imported: Vec<Task> = Vec::new();
+        let mut skipped: usize = 0;
+        for line in BufReader::new(file).lines()
+        {
+            let line: String = match line
+            {
+                Ok(line) => line,
+                Err(e) => return Err(TaskError::Io(format!("Error to read file \"{}\": {}", path, e)))
+            };
+            if line.trim().is_empty()
+            {
+                continue;
+            }
+            match serde_json::from_str::<Task>(&line)
+            {
+                Ok(task) => imported.push(task),
+                Err(_)   => skipped += 1
+            }
+        }
+
+        let count: usize = imported.len();
+        self.has_room(count)?;
+        for mut task in imported
+        {
+            // Ids are handed out anew, so blocker ids would point at unrelated tasks
+            task.blocked_by = None;
+            task.fill_modified_time();
+            self.push(task)?;
+        }
+        Ok((count, skipped))
+    }
+
+    fn to_file(&self) -> TaskFile<&Vec<Task>, &HashMap<String, Vec<Task>>>
+    {
+        return TaskFile {
+            version: FILE_VERSION,
+            next_id: self.next_id,
+            project: self.projects.active.clone(),
+            projects: &self.projects.map,
+            tasks: &self.tasks
+        };
+    }
+
+    /// Replaces the current list with tasks loaded from a file
+    fn replace_tasks(&mut self, data: TaskFile<Vec<Task>>)
+    {
+        // Snapshots were taken of a list that is gone now, undo must not bring it back
+        self.undo_stack.clear();
+        // The timed id would point at whatever task of the new list has it
+        self.timer = None;
+        // Never hand out an id that is already taken, even if the file was edited by hand
+        let max_id: u64 = data.tasks
+            .iter()
+            .chain(data.projects.values().flatten())
+            .map(|task: &Task| task.id)
+            .max()
+            .unwrap_or(0);
+        self.next_id = data.next_id.max(max_id + 1);
+        self.tasks = data.tasks;
+        self.projects = Projects { active: data.project, map: data.projects };
+        for task in self.tasks.iter_mut().chain(self.projects.map.values_mut().flatten()).filter(|task: &&mut Task| task.id == 0)
+        {
+            task.id = self.next_id;
+            self.next_id += 1;
+        }
+    }
+
+    fn read_from_file(&mut self, path: &str) -> Result<(), TaskError>
+    {
+        self.read_from_file_with(path, true)?;
+        Ok(())
+    }
+
+    /// Replaces the list with the previous version of `path` kept by `store_to_file` or `store_to_yaml`
+    fn restore_backup(&mut self, path: &str) -> Result<(), TaskError>
+    {
+        let data: TaskFile<Vec<Task>> = Self::load_file(&backup_path(path))?;
+        self.replace_tasks(data);
+        Ok(())
+    }
+
+    /// Loads the tasks stored in `path`, either replacing the list or appending with fresh ids,
+    /// and returns how many were loaded
+    fn read_from_file_with(&mut self, path: &str, replace: bool) -> Result<usize, TaskError>
+    {
+        let data: TaskFile<Vec<Task>> = Self::load_file(path)?;
+        let count: usize = data.tasks.len();
+        if replace
+        {
+            self.replace_tasks(data);
+            return Ok(count);
+        }
+
+        self.has_room(count)?;
+        for mut task in data.tasks
+        {
+            // Ids are handed out anew, so blocker ids would point at unrelated tasks
+            task.blocked_by = None;
+            self.push(task)?;
+        }
+        Ok(count)
+    }
+
+    /// Stores as YAML or JSON depending on the extension of `path`
+    fn store_to_path(&self, path: &str, pretty: bool) -> Result<(), TaskError>
+    {
+        if is_yaml_path(path)
+        {
+            return self.store_to_yaml(path);
+        }
+        return self.store_to_file(path, pretty);
+    }
+
+    fn store_to_yaml(&self, path: &str) -> Result<(), TaskError>
+    {
+        let data: TaskFile<&Vec<Task>, &HashMap<String, Vec<Task>>> = self.to_file();
+        match serde_yaml::to_string(&data)
+        {
+            Ok(content) => {
+                back_up(path)?;
+                write_atomically(path, content.as_bytes())
+            },
+            Err(e) => Err(TaskError::Io(format!("Error to write file \"{}\": {}", path, e)))
+        }
+    }
+
+    /// Reads a tasks file in either JSON or YAML, whatever its extension
+    fn load_file(path: &str) -> Result<TaskFile<Vec<Task>>, TaskError>
+    {
+        if !Path::new(path).exists()
+        {
+            return Err(TaskError::Io(format!("File \"{}\" does not exist", path)));
+        }
+
+        let content: String = match std::fs::read_to_string(path)
+        {
+            Ok(content) => content,
+            Err(e) => return Err(TaskError::Io(format!("Error to open file: {}", e)))
+        };
+
+        let data: StoredFile = match serde_json::from_str(&content)
+        {
+            Ok(data) => data,
+            Err(json_error) => match serde_yaml::from_str(&content)
+            {
+                Ok(data) => data,
+                Err(yaml_error) => return Err(TaskError::Parse(format!(
+                    "Error to read file: not valid JSON ({}) or YAML ({})", json_error, yaml_error
+                )))
+            }
+        };
+        let mut data: TaskFile<Vec<Task>> = data.migrate()?;
+        for task in data.tasks.iter_mut().chain(data.projects.values_mut().flatten())
+        {
+            task.fill_modified_time();
+        }
+        return Ok(data);
+    }
+
+    /// Adds a Medium priority task for every non-empty line of a plain text file
+    fn import_lines(&mut self, path: &str) -> Result<usize, TaskError>
+    {
+        let content: String = match std::fs::read_to_string(path)
+        {
+            Ok(content) => content,
+            Err(e) => return Err(TaskError::Io(format!("Error to read file \"{}\": {}", path, e)))
+        };
+
+        let lines: Vec<&str> = content.lines().map(str::trim).filter(|line: &&str| !line.is_empty()).collect();
+        self.has_room(lines.len())?;
+        for line in lines.iter()
+        {
+            self.push(self.new_task(line.to_string(), String::new(), Priority::Medium, None))?;
+        }
+        Ok(lines.len())
+    }
+
+    /// Appends the tasks stored in `path`, giving them fresh ids
+    fn merge_from_file(&mut self, path: &str) -> Result<usize, TaskError>
+    {
+        return self.read_from_file_with(path, false);
+    }
+}
+
+
+/// One change made during the session
+struct LogEntry
+{
+    action: String,
+    time: DateTime<Local>
+}
+
+struct ConsoleForTask
+{
+    my_tasks: TaskManager,
+    /// Loaded on startup, saved on exit and offered by the store/read commands
+    path: String,
+    /// Save to `path` on exit, off if it could not be loaded on startup
+    autosave: bool,
+    /// Color priorities with ANSI codes, off when stdout is not a terminal
+    use_color: bool,
+    /// Indent JSON files written by the store command and on exit
+    pretty_json: bool,
+    /// Order used by the plain task listing
+    sort_key: SortKey,
+    /// List completed tasks too instead of only counting them
+    show_completed: bool,
+    /// List one line per task instead of the detailed view
+    compact: bool,
+    /// Clear the screen and reprint the menu before each command's output
+    clear_screen: bool,
+    reader: Box<dyn BufRead>,
+    writer: Box<dyn Write>,
+    /// Adds, removals and edits of this session, oldest first
+    log: Vec<LogEntry>
+}
+
+impl ConsoleForTask
+{
+    /// Console on `DEFAULT_PATH`, ignoring the user's config
+    #[cfg(test)]
+    fn new() -> Self
+    {
+        return Self::with_path(DEFAULT_PATH.to_string());
+    }
+
+    fn with_config(config: AppConfig) -> Self
+    {
+        let mut console: Self = Self::with_path(config.default_path);
+        console.sort_key = config.default_sort;
+        console.use_color = console.use_color && config.use_color;
+        console.pretty_json = config.pretty_json;
+        console.my_tasks.max_tasks = config.max_tasks;
+        // Escape codes would only clutter piped output
+        console.clear_screen = config.clear_screen && io::stdout().is_terminal();
+        return console;
+    }
+
+    /// Console talking to the terminal through stdin and stdout
+    fn with_path(path: String) -> Self
+    {
+        let mut console: Self = Self::with_io(path, Box::new(BufReader::new(io::stdin())), Box::new(io::stdout()));
+        console.use_color = io::stdout().is_terminal();
+        return console;
+    }
+
+    /// Console reading commands from `reader` and writing all output to `writer`
+    fn with_io(path: String, reader: Box<dyn BufRead>, mut writer: Box<dyn Write>) -> Self
+    {
+        let mut my_tasks: TaskManager = TaskManager::new();
+        let mut autosave: bool = true;
+        if Path::new(&path).exists()
+        {
+            if let Err(e) = my_tasks.read_from_file(&path)
+            {
+                // Saving the empty list on exit would overwrite the tasks that failed to load
+                let _ = writeln!(writer, "{}\nTasks will not be saved to \"{}\" on exit", e, path);
+                autosave = false;
+            }
+        }
+
+        Self {
+            my_tasks,
+            path,
+            autosave,
+            use_color: false,
+            pretty_json: true,
+            sort_key: SortKey::Priority,
+            show_completed: false,
+            compact: false,
+            clear_screen: false,
+            reader,
+            writer,
+            log: Vec::new()
+        }
+    }
+
+    fn record(&mut self, action: String)
+    {
+        self.log.push(LogEntry { action, time: self.my_tasks.clock.now() });
+    }
+
+    fn print_menu(&mut self) -> io::Result<()>
+    {
+        writeln!(self.writer, "\nh - for help \n\n1. Add Task \n2. Pop Task \n3. Remove Task \n4. Find Task")?;
+        writeln!(self.writer, "5. List of Tasks \n6. Remove all Tasks \n7. Store Tasks to file \n8. Read Tasks from file \n9. Exit")?;
+        writeln!(self.writer, "10. List of Tasks in chosen order \n11. Toggle Task completion \n12. List of overdue Tasks \n13. Edit Task \n14. Remove Task by id")?;
+        writeln!(self.writer, "15. Search Tasks \n16. List of Tasks with minimum priority \n17. Export Tasks to CSV \n18. Import Tasks from CSV \n19. Statistics")?;
+        writeln!(self.writer, "20. Undo last removal \n21. Merge Tasks from file \n22. List of Tasks with tag \n23. Remove completed Tasks \n24. List of Tasks by pages")?;
+        writeln!(self.writer, "25. Toggle colored output \n26. Move Task up \n27. Move Task down \n28. Add subtask \n29. Toggle subtask")?;
+        writeln!(self.writer, "30. Toggle case-sensitive names \n31. Duplicate Task \n32. Toggle pretty JSON \n33. Archive completed Tasks \n34. Rename Task")?;
+        writeln!(self.writer, "35. List of Tasks grouped by priority \n36. Change Task priority \n37. Import Tasks from text file \n38. List of Tasks due today \n39. List of Tasks with details")?;
+        writeln!(self.writer, "40. List of Tasks due soon \n41. Swap two Tasks \n42. Peek at top Task \n43. What should I do next? \n44. Remove duplicate Tasks")?;
+        writeln!(self.writer, "45. Export Tasks to JSON lines \n46. Import Tasks from JSON lines \n47. List of Tasks added between dates \n48. Complete Task \n49. Reopen Task")?;
+        writeln!(self.writer, "50. Session history \n51. Export Tasks to Markdown \n52. Snooze Task \n53. Toggle showing completed Tasks \n54. Remove Task by number")?;
+        writeln!(self.writer, "55. Show Task by number \n56. Show oldest Task \n57. Restore Tasks from backup \n58. Priority histogram \n59. Create project")?;
+        writeln!(self.writer, "60. Switch project \n61. Delete project \n62. Move Task to project \n63. Set blocking Task \n64. Remove Tasks completed long ago")?;
+        writeln!(self.writer, "65. Toggle compact list view \n66. Tag summary \n67. Start timer on Task \n68. Stop timer \n69. Reschedule overdue Tasks to today")?;
+        writeln!(self.writer, "70. Export Task to file \n71. Import Task from file \n72. List of recently added Tasks")?;
+        Ok(())
+    }
+
+    fn input(&mut self, query: &str) -> io::Result<String>
+    {
+        write!(self.writer, "{}", query)?;
+        self.writer.flush()?;
+
+        let mut buffer: String = String::new();
+        if self.reader.read_line(&mut buffer)? == 0
+        {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "end of input"));
+        }
+        
+        return Ok(buffer.to_string());
+    }
+
+    /// Reads lines until one containing only "." (or end of input) and joins them with '\n'
+    fn read_multiline(reader: &mut impl BufRead) -> io::Result<String>
+    {
+        let mut lines: Vec<String> = Vec::new();
+        loop
+        {
+            let mut buffer: String = String::new();
+            if reader.read_line(&mut buffer)? == 0
+            {
+                break;
+            }
+
+            let line: &str = buffer.trim_end_matches(['\r', '\n']);
+            if line.trim() == "."
+            {
+                break;
+            }
+            lines.push(line.to_string());
+        }
+        return Ok(lines.join("\n"));
+    }
+
+    fn input_multiline(&mut self, query: &str) -> io::Result<String>
+    {
+        writeln!(self.writer, "{}", query)?;
+        return Self::read_multiline(&mut self.reader);
+    }
+
+    fn is_affirmative(answer: &str) -> bool
+    {
+        return matches!(answer.trim().to_lowercase().as_str(), "y" | "yes");
+    }
+
+    /// Asks a yes/no question, anything but "y" or "yes" counts as no
+    fn confirm(&mut self, query: &str) -> bool
+    {
+        return match self.input(&format!("{} (y/n): ", query))
+        {
+            Ok(answer) => Self::is_affirmative(&answer),
+            Err(_)     => false
+        };
+    }
+
+    /// Returns the trimmed input, or `default` when the user just presses Enter
+    fn input_or_default(&mut self, query: &str, default: &str) -> io::Result<String>
+    {
+        let input: String = self.input(&format!("{}[{}]: ", query, default))?;
+        if input.trim().is_empty()
+        {
+            return Ok(default.to_string());
+        }
+        return Ok(input.trim().to_string());
+    }
+
+    /// Lists up to three task names close to a name that wasn't found
+    fn suggest_names(&mut self, name: &str) -> io::Result<()>
+    {
+        let names: Vec<String> = self.my_tasks.find_fuzzy(name)
+            .into_iter()
+            .filter(|(_, distance): &(usize, usize)| *distance <= 3)
+            .take(3)
+            .map(|(index, _)| format!("\"{}\"", self.my_tasks.tasks[index].name))
+            .collect();
+        if !names.is_empty()
+        {
+            writeln!(self.writer, "Did you mean {}?", names.join(", "))?;
+        }
+        Ok(())
+    }
+
+    /// Asks until the user enters a valid priority, an empty answer keeps `default`
+    fn prompt_priority(&mut self, default: Priority) -> io::Result<Priority>
+    {
+        let label: String = match default
+        {
+            Priority::None => "None".to_string(),
+            priority       => priority.to_string()
+        };
+        loop
+        {
+            let input: String = self.input_or_default("Enter index of priority (0. None, 1. Low, 2. Medium, 3. High, 4. Very High) ", &label)?;
+            if let Ok(priority) = input.parse::<Priority>()
+            {
+                return Ok(priority);
+            }
+        }
+    }
+
+    /// Lists the tasks in the chosen view
+    fn print_tasks(&mut self) -> io::Result<()>
+    {
+        if self.compact
+        {
+            return self.my_tasks.print_compact(&mut self.writer, self.show_completed);
+        }
+        return self.my_tasks.print(&mut self.writer, self.use_color, self.show_completed);
+    }
+
+    /// Shows the task names numbered from 1 and returns the index picked by the user
+    fn select_task(&mut self) -> io::Result<Option<usize>>
+    {
+        if self.my_tasks.tasks.is_empty()
+        {
+            writeln!(self.writer, "List of tasks is empty")?;
+            return Ok(None);
+        }
+        for (index, task) in self.my_tasks.tasks.iter().enumerate()
+        {
+            writeln!(self.writer, "{}. {}", index + 1, task.name)?;
+        }
+        let input: String = self.input("Enter number of task: ")?;
+        match input.trim().parse::<usize>()
+        {
+            Ok(number) if number > 0 => Ok(Some(number - 1)),
+            _ => {
+                writeln!(self.writer, "Invalid number")?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Asks until the user enters a valid dd-mm-yyyy date
+    fn input_date(&mut self, query: &str) -> io::Result<NaiveDate>
+    {
+        loop
+        {
+            let input: String = self.input(query)?;
+            match NaiveDate::parse_from_str(input.trim(), "%d-%m-%Y")
+            {
+                Ok(date) => return Ok(date),
+                Err(_)   => writeln!(self.writer, "Invalid date format")?
+            }
+        }
+    }
+
+    /// Saves the tasks to the console's path when the session ends
+    fn save_on_exit(&mut self)
+    {
+        if let Some((name, elapsed)) = self.my_tasks.stop_timer()
+        {
+            let _ = writeln!(self.writer, "Timer of task \"{}\" stopped after {}", name, format_time_spent(elapsed));
+        }
+        if !self.autosave
+        {
+            let _ = writeln!(self.writer, "Tasks not saved, \"{}\" could not be loaded on startup", self.path);
+            return;
+        }
+        let _ = match self.my_tasks.store_to_path(&self.path, self.pretty_json)
+        {
+            Ok(_)  => writeln!(self.writer, "Tasks saved to \"{}\"", self.path),
+            Err(e) => writeln!(self.writer, "{}", e)
+        };
+    }
+
+    /// Handles one command, returns false once the session should end
+    fn process_input(&mut self) -> bool
+    {
+        match self.run_command()
+        {
+            Ok(keep_running) => return keep_running,
+            // Input was closed, e.g. the end of a piped script
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                let _ = writeln!(self.writer);
+                self.save_on_exit();
+                return false;
+            },
+            Err(e) => {
+                let _ = writeln!(self.writer, "Error user input: {e}");
+            }
+        };
+        return true;
+    }
+
+    fn run_command(&mut self) -> io::Result<bool>
+    {
+        let command: String = self.input("\nEnter command index: ")?;
+        if self.clear_screen
+        {
+            // Clear, move the cursor home, then keep the menu above the command's output
+            write!(self.writer, "\x1b[2J\x1b[H")?;
+            self.print_menu()?;
+        }
+        match command.trim()
+        {
+            "h" => self.print_menu()?,
+            "1" => {
+                let mut name: String = self.input("Enter name of new task: ")?.trim().to_string();
+                loop
+                {
+                    if name.is_empty()
+                    {
+                        writeln!(self.writer, "{}", TaskError::Empty)?;
+                    }
+                    else if self.my_tasks.find(&name).is_some()
+                    {
+                        writeln!(self.writer, "Task \"{}\" already exists", name)?;
+                    }
+                    else
+                    {
+                        break;
+                    }
+                    name = self.input("Enter a different name: ")?.trim().to_string();
+                }
+                let description: String = self.input_multiline("Enter description (finish with a line containing only \".\"):")?
+                    .trim()
+                    .to_string();
+                let mut task: Task = self.my_tasks.new_task(name, description, Priority::None, None);
+                loop
+                {
+                    let input: String = self.input("Enter due date (dd-mm-yyyy hh:mm, today, tomorrow, +3d, +4h) or leave empty: ")?;
+                    if input.trim().is_empty()
+                    {
+                        break;
+                    }
+                    match parse_due(&input, self.my_tasks.clock.now())
+                    {
+                        Ok(date) => {
+                            task.due_date = Some(date);
+                            match task.validate()
+                            {
+                                Ok(_)  => break,
+                                Err(e) => writeln!(self.writer, "{}", e)?
+                            }
+                            task.due_date = None;
+                        },
+                        Err(e) => writeln!(self.writer, "{}", e)?
+                    }
+                }
+                let priority: Priority = self.prompt_priority(Priority::Medium)?;
+                let tags: String = self.input("Enter tags separated by commas or leave empty: ")?;
+                let recurrence: Option<Recurrence> = loop
+                {
+                    match self.input("Enter index of recurrence (1. Daily, 2. Weekly, 3. Monthly) or leave empty: ")?
+                        .trim()
+                    {
+                        ""  => break None,
+                        "1" => break Some(Recurrence::Daily),
+                        "2" => break Some(Recurrence::Weekly),
+                        "3" => break Some(Recurrence::Monthly),
+                        _   => continue,
+                    }
+                };
+                let color: Option<TaskColor> = loop
+                {
+                    match self.input("Enter index of color label (1. Red, 2. Green, 3. Yellow, 4. Blue, 5. Magenta, 6. Cyan) or leave empty: ")?
+                        .trim()
+                    {
+                        ""  => break None,
+                        "1" => break Some(TaskColor::Red),
+                        "2" => break Some(TaskColor::Green),
+                        "3" => break Some(TaskColor::Yellow),
+                        "4" => break Some(TaskColor::Blue),
+                        "5" => break Some(TaskColor::Magenta),
+                        "6" => break Some(TaskColor::Cyan),
+                        _   => continue,
+                    }
+                };
+                let progress: u8 = loop
+                {
+                    let input: String = self.input("Enter progress in percent or leave empty for 0: ")?;
+                    if input.trim().is_empty()
+                    {
+                        break 0;
+                    }
+                    match Task::parse_progress(&input)
+                    {
+                        Ok(progress) => break progress,
+                        Err(e)       => writeln!(self.writer, "{}", e)?
+                    }
+                };
+                task.priority = priority;
+                task.tags = Task::parse_tags(&tags);
+                task.recurrence = recurrence;
+                task.color = color;
+                task.progress = progress;
+                let added: String = task.name.clone();
+                match self.my_tasks.add(task)
+                {
+                    Ok(_)  => self.record(format!("Added task \"{}\"", added)),
+                    Err(e) => writeln!(self.writer, "{}", e)?
+                }
+            },
+            "2" => {
+                match self.my_tasks.pop()
+                {
+                    Some(task) => {
+                        writeln!(self.writer, "Task \"{}\" removed", task.name)?;
+                        self.record(format!("Removed task \"{}\"", task.name));
+                    },
+                    None => writeln!(self.writer, "List of tasks is empty")?,
+                }
+            },
+            "3" => {
+                let name: String = self.input("Enter name of task that you wanna remove: ")?;
+                match self.my_tasks.remove(name.trim())
+                {
+                    Ok(task) => {
+                        writeln!(self.writer, "Task \"{}\" removed", task.name)?;
+                        self.record(format!("Removed task \"{}\"", task.name));
+                    },
+                    Err(e) => {
+                        writeln!(self.writer, "{}", e)?;
+                        if let TaskError::NotFound(_) = e
+                        {
+                            self.suggest_names(name.trim())?;
+                        }
+                    }
+                }
+            },
+            "4" => {
+                let name: String = self.input("Enter name of task that you wanna find: ")?;
+                let indices: Vec<usize> = self.my_tasks.find_all(name.trim());
+                if indices.is_empty()
+                {
+                    writeln!(self.writer, "Task \"{}\" not found", name.trim())?;
+                    self.suggest_names(name.trim())?;
+                }
+                for index in indices
+                {
+                    self.my_tasks.print_task(&mut self.writer, &self.my_tasks.tasks[index], self.use_color)?;
+                    writeln!(self.writer)?;
+                }
+            },
+            "5" => {
+                self.my_tasks.sort(self.sort_key);
+                self.print_tasks()?;
+            },
+            "6" => {
+                if self.confirm("Remove all tasks?")
+                {
+                    self.my_tasks.clear();
+                    writeln!(self.writer, "All tasks removed")?;
+                    self.record("Removed all tasks".to_string());
+                }
+            },
+            "7" => {
+                let path: String = self.input_or_default("Enter path to file where to store tasks ", &self.path.clone())?;
+                match self.my_tasks.store_to_path(&path, self.pretty_json)
+                {
+                    // The unreadable file is gone now, so saving on exit is safe again
+                    Ok(_) if path == self.path => self.autosave = true,
+                    Ok(_)  => {},
+                    Err(e) => writeln!(self.writer, "{}", e)?
+                }
+            },
+            "8" => {
+                let path: String = self.input_or_default("Enter path to file that store tasks ", &self.path.clone())?;
+                // Replacing stays the default, as it was before merging was offered here
+                let merge: bool = self.confirm("Add to current tasks instead of replacing them?");
+                match self.my_tasks.read_from_file_with(&path, !merge)
+                {
+                    Ok(count) => writeln!(self.writer, "{} tasks loaded", count)?,
+                    Err(e)    => writeln!(self.writer, "{}", e)?
+                }
+            }
+            "9" => {
+                self.save_on_exit();
+                return Ok(false);
+            },
+            "10" => {
+                let mut key: Option<SortKey> = None;
+                while key.is_none()
+                {
+                    key = match self.input("Enter index of sort order (1. Priority, 2. Name, 3. Add time): ")?
+                        .trim()
+                    {
+                        "1" => Some(SortKey::Priority),
+                        "2" => Some(SortKey::Name),
+                        "3" => Some(SortKey::AddTime),
+                        _   => None,
+                    }
+                }
+                self.my_tasks.sort(key.unwrap());
+                self.print_tasks()?;
+            },
+            "11" => {
+                let name: String = self.input("Enter name of task to toggle completion: ")?;
+                match self.my_tasks.toggle_complete(name.trim())
+                {
+                    Ok(_)  => writeln!(self.writer, "Task \"{}\" toggled", name.trim())?,
+                    Err(e) => writeln!(self.writer, "{}", e)?
+                }
+            },
+            "12" => {
+                let overdue: Vec<&Task> = self.my_tasks.overdue();
+                if overdue.is_empty()
+                {
+                    writeln!(self.writer, "No overdue tasks")?;
+                }
+                let now: DateTime<Local> = self.my_tasks.clock.now();
+                for task in overdue
+                {
+                    self.my_tasks.print_task(&mut self.writer, task, self.use_color)?;
+                    if let Some(due_date) = task.due_date
+                    {
+                        writeln!(self.writer, "Overdue by {}", format_duration(now - due_date))?;
+                    }
+                    writeln!(self.writer)?;
+                }
+            },
+            "13" => {
+                let name: String = self.input("Enter name of task that you wanna edit: ")?.trim().to_string();
+                match self.my_tasks.find(&name)
+                {
+                    Some(index) => {
+                        writeln!(self.writer, "Press Enter to keep current value")?;
+                        self.my_tasks.print_task(&mut self.writer, &self.my_tasks.tasks[index], self.use_color)?;
+
+                        let new_name: String = self.input("Enter new name: ")?.trim().to_string();
+                        let new_desc: String = self.input("Enter new description: ")?.trim().to_string();
+                        let new_priority: Priority = self.prompt_priority(self.my_tasks.tasks[index].priority)?;
+
+                        match self.my_tasks.edit(
+                            &name,
+                            if new_name.is_empty() { None } else { Some(new_name) },
+                            if new_desc.is_empty() { None } else { Some(new_desc) },
+                            Some(new_priority)
+                        )
+                        {
+                            Ok(_)  => {
+                                writeln!(self.writer, "Task \"{}\" edited", name)?;
+                                self.record(format!("Edited task \"{}\"", name));
+                            },
+                            Err(e) => writeln!(self.writer, "{}", e)?
+                        }
+                    },
+                    None => writeln!(self.writer, "Task \"{}\" not found", name)?
+                }
+            },
+            "14" => {
+                let id: String = self.input("Enter id of task that you wanna remove: ")?;
+                match id.trim().trim_start_matches('#').parse::<u64>()
+                {
+                    Ok(id) => match self.my_tasks.remove_by_id(id)
+                    {
+                        Ok(task) => {
+                            writeln!(self.writer, "Task \"{}\" removed", task.name)?;
+                            self.record(format!("Removed task \"{}\"", task.name));
+                        },
+                        Err(e) => writeln!(self.writer, "{}", e)?
+                    },
+                    Err(_) => writeln!(self.writer, "Invalid id")?
+                }
+            },
+            "15" => {
+                let query: String = self.input("Enter text to search for: ")?;
+                let matches: Vec<&Task> = self.my_tasks.search(query.trim());
+                if matches.is_empty()
+                {
+                    writeln!(self.writer, "No matches found")?;
+                }
+                for task in matches
+                {
+                    self.my_tasks.print_task(&mut self.writer, task, self.use_color)?;
+                    writeln!(self.writer)?;
+                }
+            },
+            "16" => {
+                let mut min: Priority = Priority::None;
+                while min == Priority::None
+                {
+                    min = self.input("Enter index of minimum priority (1. Low, 2. Medium, 3. High, 4. Very High): ")?
+                        .parse()
+                        .unwrap_or(Priority::None);
+                }
+                for task in self.my_tasks.filter_by_priority(min)
+                {
+                    self.my_tasks.print_task(&mut self.writer, task, self.use_color)?;
+                    writeln!(self.writer)?;
+                }
+            },
+            "17" => {
+                let path: String = self.input("Enter path to CSV file: ")?;
+                match self.my_tasks.export_csv(path.trim())
+                {
+                    Ok(_)  => writeln!(self.writer, "Tasks exported to \"{}\"", path.trim())?,
+                    Err(e) => writeln!(self.writer, "{}", e)?
+                }
+            },
+            "18" => {
+                let path: String = self.input("Enter path to CSV file: ")?;
+                match self.my_tasks.import_csv(path.trim())
+                {
+                    Ok(count) => {
+                        writeln!(self.writer, "{} tasks imported", count)?;
+                        self.record(format!("Imported {} tasks from \"{}\"", count, path.trim()));
+                    },
+                    Err(e)    => writeln!(self.writer, "{}", e)?
+                }
+            },
+            "19" => {
+                let stats: TaskStats = self.my_tasks.stats();
+                writeln!(self.writer, "Total:     {}", stats.total)?;
+                writeln!(self.writer, "Very High: {}", stats.very_high)?;
+                writeln!(self.writer, "High:      {}", stats.high)?;
+                writeln!(self.writer, "Medium:    {}", stats.medium)?;
+                writeln!(self.writer, "Low:       {}", stats.low)?;
+                if stats.none > 0
+                {
+                    writeln!(self.writer, "None:      {}", stats.none)?;
+                }
+                writeln!(self.writer, "Completed: {}", stats.completed)?;
+                writeln!(self.writer, "Overdue:   {}", stats.overdue)?;
+                writeln!(self.writer, "Progress:  {:.1}%", self.my_tasks.average_progress())?;
+            },
+            "20" => {
+                match self.my_tasks.undo()
+                {
+                    Ok(_)  => writeln!(self.writer, "Last removal undone")?,
+                    Err(e) => writeln!(self.writer, "{}", e)?
+                }
+            },
+            "21" => {
+                let path: String = self.input("Enter path to file to merge tasks from: ")?;
+                match self.my_tasks.merge_from_file(path.trim())
+                {
+                    Ok(count) => {
+                        writeln!(self.writer, "{} tasks merged", count)?;
+                        self.record(format!("Imported {} tasks from \"{}\"", count, path.trim()));
+                    },
+                    Err(e)    => writeln!(self.writer, "{}", e)?
+                }
+            },
+            "22" => {
+                let tag: String = self.input("Enter tag: ")?;
+                let matches: Vec<&Task> = self.my_tasks.filter_by_tag(tag.trim());
+                if matches.is_empty()
+                {
+                    writeln!(self.writer, "No tasks tagged \"{}\"", tag.trim())?;
+                }
+                for task in matches
+                {
+                    self.my_tasks.print_task(&mut self.writer, task, self.use_color)?;
+                    writeln!(self.writer)?;
+                }
+            },
+            "23" => {
+                let count: usize = self.my_tasks.remove_completed();
+                writeln!(self.writer, "{} completed tasks removed", count)?;
+                if count > 0
+                {
+                    self.record(format!("Removed {} completed tasks", count));
+                }
+            },
+            "24" => {
+                let mut page: usize = 1;
+                loop
+                {
+                    self.my_tasks.print_page(&mut self.writer, page, PER_PAGE, self.use_color)?;
+                    match self.input("n - next page, p - previous page, q - quit: ")?.trim()
+                    {
+                        "n" => page = (page + 1).min(self.my_tasks.page_count(PER_PAGE)),
+                        "p" => page = page.saturating_sub(1).max(1),
+                        "q" => break,
+                        _   => writeln!(self.writer, "Invalid input")?
+                    }
+                }
+            },
+            "25" => {
+                self.use_color = !self.use_color;
+                writeln!(self.writer, "Colored output {}", if self.use_color { "on" } else { "off" })?;
+            },
+            "26" => {
+                let name: String = self.input("Enter name of task to move up: ")?;
+                if let Err(e) = self.my_tasks.move_up(name.trim())
+                {
+                    writeln!(self.writer, "{}", e)?;
+                }
+            },
+            "27" => {
+                let name: String = self.input("Enter name of task to move down: ")?;
+                if let Err(e) = self.my_tasks.move_down(name.trim())
+                {
+                    writeln!(self.writer, "{}", e)?;
+                }
+            },
+            "28" => {
+                let name: String = self.input("Enter name of task to add a subtask to: ")?;
+                let title: String = self.input("Enter subtask: ")?.trim().to_string();
+                if let Err(e) = self.my_tasks.add_subtask(name.trim(), title)
+                {
+                    writeln!(self.writer, "{}", e)?;
+                }
+            },
+            "29" => {
+                let name: String = self.input("Enter name of task: ")?;
+                match self.my_tasks.find(name.trim())
+                {
+                    Some(index) => {
+                        for (number, subtask) in self.my_tasks.tasks[index].subtasks.iter().enumerate()
+                        {
+                            writeln!(self.writer, "{}. {} {}", number + 1, if subtask.done { "[x]" } else { "[ ]" }, subtask.title)?;
+                        }
+                        let number: String = self.input("Enter number of subtask to toggle: ")?;
+                        match number.trim().parse::<usize>()
+                        {
+                            Ok(number) => if let Err(e) = self.my_tasks.toggle_subtask(name.trim(), number)
+                            {
+                                writeln!(self.writer, "{}", e)?;
+                            },
+                            Err(_) => writeln!(self.writer, "Invalid number")?
+                        }
+                    },
+                    None => writeln!(self.writer, "Task \"{}\" not found", name.trim())?
+                }
+            },
+            "30" => {
+                self.my_tasks.match_case = !self.my_tasks.match_case;
+                writeln!(self.writer, "Case-sensitive names {}", if self.my_tasks.match_case { "on" } else { "off" })?;
+            },
+            "31" => {
+                let name: String = self.input("Enter name of task to duplicate: ")?;
+                match self.my_tasks.duplicate(name.trim())
+                {
+                    Ok(_)  => self.record(format!("Duplicated task \"{}\"", name.trim())),
+                    Err(e) => writeln!(self.writer, "{}", e)?
+                }
+            },
+            "32" => {
+                self.pretty_json = !self.pretty_json;
+                writeln!(self.writer, "Pretty JSON {}", if self.pretty_json { "on" } else { "off" })?;
+            },
+            "33" => {
+                let path: String = self.input_or_default("Enter path to archive file ", ARCHIVE_PATH)?;
+                match self.my_tasks.archive_completed(&path)
+                {
+                    Ok(count) => {
+                        writeln!(self.writer, "{} completed tasks archived to \"{}\"", count, path)?;
+                        if count > 0
+                        {
+                            self.record(format!("Archived {} completed tasks to \"{}\"", count, path));
+                        }
+                    },
+                    Err(e)    => writeln!(self.writer, "{}", e)?
+                }
+            },
+            "34" => {
+                let old: String = self.input("Enter name of task to rename: ")?;
+                let new: String = self.input("Enter new name: ")?;
+                match self.my_tasks.rename(old.trim(), new.trim())
+                {
+                    Ok(_)  => {
+                        writeln!(self.writer, "Task \"{}\" renamed to \"{}\"", old.trim(), new.trim())?;
+                        self.record(format!("Renamed task \"{}\" to \"{}\"", old.trim(), new.trim()));
+                    },
+                    Err(e) => writeln!(self.writer, "{}", e)?
+                }
+            },
+            "35" => {
+                for (priority, tasks) in self.my_tasks.grouped_by_priority()
+                {
+                    match priority
+                    {
+                        Priority::None => writeln!(self.writer, "=== No priority ===")?,
+                        _              => writeln!(self.writer, "=== {} ===", priority)?
+                    }
+                    for task in tasks
+                    {
+                        self.my_tasks.print_task(&mut self.writer, task, self.use_color)?;
+                        writeln!(self.writer)?;
+                    }
+                }
+            },
+            "36" => {
+                let name: String = self.input("Enter name of task: ")?;
+                let priority: String = self.input("Enter new priority (0. None, 1. Low, 2. Medium, 3. High, 4. Very High): ")?;
+                match priority.parse::<Priority>()
+                {
+                    Ok(priority) => match self.my_tasks.set_priority(name.trim(), priority)
+                    {
+                        Ok(_)  => {
+                            writeln!(self.writer, "Priority of task \"{}\" changed", name.trim())?;
+                            self.record(format!("Changed priority of task \"{}\" to {}", name.trim(), priority));
+                        },
+                        Err(e) => writeln!(self.writer, "{}", e)?
+                    },
+                    Err(e) => writeln!(self.writer, "{}", e)?
+                }
+            },
+            "37" => {
+                let path: String = self.input("Enter path to text file with one task per line: ")?;
+                match self.my_tasks.import_lines(path.trim())
+                {
+                    Ok(count) => {
+                        writeln!(self.writer, "{} tasks imported", count)?;
+                        self.record(format!("Imported {} tasks from \"{}\"", count, path.trim()));
+                    },
+                    Err(e)    => writeln!(self.writer, "{}", e)?
+                }
+            },
+            "38" => {
+                let today: Vec<&Task> = self.my_tasks.due_today();
+                if today.is_empty()
+                {
+                    writeln!(self.writer, "Nothing due today")?;
+                }
+                for task in today
+                {
+                    self.my_tasks.print_task(&mut self.writer, task, self.use_color)?;
+                    writeln!(self.writer)?;
+                }
+            },
+            "39" => {
+                for task in self.my_tasks.tasks.iter()
+                {
+                    self.my_tasks.print_task(&mut self.writer, task, self.use_color)?;
+                    writeln!(self.writer, "Words: {}", task.description_word_count())?;
+                    writeln!(self.writer, "Open for: {}", format_duration(task.age(self.my_tasks.clock.now())))?;
+                    writeln!(self.writer)?;
+                }
+                writeln!(self.writer, "Total words: {}", self.my_tasks.total_words())?;
+            },
+            "40" => {
+                let hours: String = self.input_or_default("Enter number of hours to look ahead ", "24")?;
+                let soon: Vec<&Task> = match hours.parse::<i64>()
+                {
+                    Ok(hours) => match self.my_tasks.due_within(hours)
+                    {
+                        Ok(soon) => soon,
+                        Err(e) => {
+                            writeln!(self.writer, "{}", e)?;
+                            return Ok(true);
+                        }
+                    },
+                    Err(_) => {
+                        writeln!(self.writer, "Invalid number of hours")?;
+                        return Ok(true);
+                    }
+                };
+                if soon.is_empty()
+                {
+                    writeln!(self.writer, "Nothing due in the next {} hours", hours)?;
+                }
+                for task in soon
+                {
+                    self.my_tasks.print_task(&mut self.writer, task, self.use_color)?;
+                    writeln!(self.writer)?;
+                }
+            },
+            "41" => {
+                let a: String = self.input("Enter name of first task: ")?;
+                let b: String = self.input("Enter name of second task: ")?;
+                match self.my_tasks.swap(a.trim(), b.trim())
+                {
+                    Ok(_)  => writeln!(self.writer, "Tasks \"{}\" and \"{}\" swapped", a.trim(), b.trim())?,
+                    Err(e) => writeln!(self.writer, "{}", e)?
+                }
+            },
+            "42" => {
+                match self.my_tasks.peek()
+                {
+                    Some(task) => self.my_tasks.print_task(&mut self.writer, task, self.use_color)?,
+                    None       => writeln!(self.writer, "List of tasks is empty")?,
+                }
+            },
+            "43" => {
+                match self.my_tasks.suggest_next()
+                {
+                    Some(task) => self.my_tasks.print_task(&mut self.writer, task, self.use_color)?,
+                    None       => writeln!(self.writer, "Nothing left to do")?,
+                }
+            },
+            "44" => {
+                let count: usize = self.my_tasks.dedupe();
+                writeln!(self.writer, "{} duplicate tasks removed", count)?;
+                if count > 0
+                {
+                    self.record(format!("Removed {} duplicate tasks", count));
+                }
+            },
+            "45" => {
+                let path: String = self.input("Enter path to JSON lines file: ")?;
+                match self.my_tasks.export_jsonl(path.trim())
+                {
+                    Ok(_)  => writeln!(self.writer, "Tasks exported to \"{}\"", path.trim())?,
+                    Err(e) => writeln!(self.writer, "{}", e)?
+                }
+            },
+            "46" => {
+                let path: String = self.input("Enter path to JSON lines file: ")?;
+                match self.my_tasks.import_jsonl(path.trim())
+                {
+                    Ok((count, skipped)) => {
+                        if skipped == 0
+                        {
+                            writeln!(self.writer, "{} tasks imported", count)?;
+                        }
+                        else
+                        {
+                            writeln!(self.writer, "{} tasks imported, {} malformed lines skipped", count, skipped)?;
+                        }
+                        self.record(format!("Imported {} tasks from \"{}\"", count, path.trim()));
+                    },
+                    Err(e) => writeln!(self.writer, "{}", e)?
+                }
+            },
+            "47" => {
+                let first: NaiveDate = self.input_date("Enter first date (dd-mm-yyyy): ")?;
+                let second: NaiveDate = self.input_date("Enter last date (dd-mm-yyyy): ")?;
+                let (first, second): (NaiveDate, NaiveDate) = (first.min(second), first.max(second));
+                // Cover both days completely
+                let from: Option<DateTime<Local>> = first.and_hms_opt(0, 0, 0)
+                    .and_then(|date: NaiveDateTime| Local.from_local_datetime(&date).earliest());
+                let to: Option<DateTime<Local>> = second.and_hms_opt(23, 59, 59)
+                    .and_then(|date: NaiveDateTime| Local.from_local_datetime(&date).latest());
+                let (from, to): (DateTime<Local>, DateTime<Local>) = match (from, to)
+                {
+                    (Some(from), Some(to)) => (from, to),
+                    _ => {
+                        writeln!(self.writer, "Invalid date")?;
+                        return Ok(true);
+                    }
+                };
+                let tasks: Vec<&Task> = self.my_tasks.added_between(from, to);
+                if tasks.is_empty()
+                {
+                    writeln!(self.writer, "No tasks added in this range")?;
+                }
+                for task in tasks
+                {
+                    self.my_tasks.print_task(&mut self.writer, task, self.use_color)?;
+                    writeln!(self.writer)?;
+                }
+            },
+            "48" => {
+                let name: String = self.input("Enter name of task to complete: ")?;
+                match self.my_tasks.complete(name.trim())
+                {
+                    Ok(_)  => writeln!(self.writer, "Task \"{}\" completed", name.trim())?,
+                    Err(e) => writeln!(self.writer, "{}", e)?
+                }
+            },
+            "49" => {
+                let name: String = self.input("Enter name of task to reopen: ")?;
+                match self.my_tasks.uncomplete(name.trim())
+                {
+                    Ok(_)  => writeln!(self.writer, "Task \"{}\" reopened", name.trim())?,
+                    Err(e) => writeln!(self.writer, "{}", e)?
+                }
+            },
+            "50" => {
+                if self.log.is_empty()
+                {
+                    writeln!(self.writer, "Nothing changed in this session")?;
+                }
+                for entry in self.log.iter()
+                {
+                    writeln!(self.writer, "{}  {}", entry.time.format("%d-%m-%Y  %H:%M:%S"), entry.action)?;
+                }
+            },
+            "51" => {
+                let path: String = self.input("Enter path to Markdown file: ")?;
+                match self.my_tasks.export_markdown(path.trim())
+                {
+                    Ok(_)  => writeln!(self.writer, "Tasks exported to \"{}\"", path.trim())?,
+                    Err(e) => writeln!(self.writer, "{}", e)?
+                }
+            },
+            "52" => {
+                let name: String = self.input("Enter name of task: ")?;
+                let days: String = self.input("Enter number of days to snooze: ")?;
+                match days.trim().parse::<i64>()
+                {
+                    Ok(days) if days > 0 => match self.my_tasks.snooze(name.trim(), days)
+                    {
+                        Ok(_)  => writeln!(self.writer, "Task \"{}\" snoozed for {} days", name.trim(), days)?,
+                        Err(e) => writeln!(self.writer, "{}", e)?
+                    },
+                    _ => writeln!(self.writer, "Invalid number of days")?
+                }
+            },
+            "53" => {
+                self.show_completed = !self.show_completed;
+                writeln!(self.writer, "Completed tasks {}", if self.show_completed { "shown" } else { "hidden" })?;
+            },
+            "54" => {
+                if let Some(index) = self.select_task()?
+                {
+                    match self.my_tasks.remove_at(index)
+                    {
+                        Ok(task) => {
+                            writeln!(self.writer, "Task \"{}\" removed", task.name)?;
+                            self.record(format!("Removed task \"{}\"", task.name));
+                        },
+                        Err(e) => writeln!(self.writer, "{}", e)?
+                    }
+                }
+            },
+            "55" => {
+                if let Some(index) = self.select_task()?
+                {
+                    match self.my_tasks.tasks.get(index)
+                    {
+                        Some(task) => self.my_tasks.print_task(&mut self.writer, task, self.use_color)?,
+                        None       => writeln!(self.writer, "No task number {}", index + 1)?
+                    }
+                }
+            },
+            "56" => {
+                match self.my_tasks.oldest()
+                {
+                    Some(task) => {
+                        self.my_tasks.print_task(&mut self.writer, task, self.use_color)?;
+                        writeln!(self.writer, "Open for: {}", format_duration(task.age(self.my_tasks.clock.now())))?;
+                    },
+                    None => writeln!(self.writer, "Nothing left to do")?,
+                }
+            },
+            "57" => {
+                let path: String = self.path.clone();
+                if self.confirm(&format!("Replace current tasks with the backup of \"{}\"?", path))
+                {
+                    match self.my_tasks.restore_backup(&path)
+                    {
+                        Ok(_)  => writeln!(self.writer, "Tasks restored from \"{}\"", backup_path(&path))?,
+                        Err(e) => writeln!(self.writer, "{}", e)?
+                    }
+                }
+            },
+            "58" => {
+                write!(self.writer, "{}", self.my_tasks.priority_histogram())?;
+            },
+            "59" => {
+                let name: String = self.input("Enter name of new project: ")?;
+                match self.my_tasks.projects.create(name.trim())
+                {
+                    Ok(_)  => writeln!(self.writer, "Project \"{}\" created", name.trim())?,
+                    Err(e) => writeln!(self.writer, "{}", e)?
+                }
+            },
+            "60" => {
+                for name in self.my_tasks.projects.names()
+                {
+                    let marker: &str = if name == self.my_tasks.projects.active { " (active)" } else { "" };
+                    writeln!(self.writer, "{}{}", name, marker)?;
+                }
+                let name: String = self.input("Enter name of project to switch to: ")?;
+                match self.my_tasks.switch_project(name.trim())
+                {
+                    Ok(_)  => writeln!(self.writer, "Switched to project \"{}\"", name.trim())?,
+                    Err(e) => writeln!(self.writer, "{}", e)?
+                }
+            },
+            "61" => {
+                let name: String = self.input("Enter name of project to delete: ")?;
+                if self.confirm(&format!("Delete project \"{}\" with all its tasks?", name.trim()))
+                {
+                    match self.my_tasks.projects.delete(name.trim())
+                    {
+                        Ok(tasks) => writeln!(self.writer, "Project \"{}\" deleted with {} tasks", name.trim(), tasks.len())?,
+                        Err(e)    => writeln!(self.writer, "{}", e)?
+                    }
+                }
+            },
+            "62" => {
+                let name: String = self.input("Enter name of task: ")?;
+                let active: String = self.my_tasks.projects.active.clone();
+                let from: String = self.input_or_default("Enter project to move it from ", &active)?;
+                let to: String = self.input("Enter project to move it to: ")?;
+                match self.my_tasks.move_task(name.trim(), &from, to.trim())
+                {
+                    Ok(_)  => writeln!(self.writer, "Task \"{}\" moved to project \"{}\"", name.trim(), to.trim())?,
+                    Err(e) => writeln!(self.writer, "{}", e)?
+                }
+            },
+            "63" => {
+                let name: String = self.input("Enter name of task: ")?;
+                let id: String = self.input("Enter id of task it waits for or leave empty for none: ")?;
+                let blocker_id: Option<u64> = match id.trim().trim_start_matches('#')
+                {
+                    ""  => None,
+                    id  => match id.parse::<u64>()
+                    {
+                        Ok(id) => Some(id),
+                        Err(_) => {
+                            writeln!(self.writer, "Invalid id")?;
+                            return Ok(true);
+                        }
+                    }
+                };
+                match self.my_tasks.set_blocker(name.trim(), blocker_id)
+                {
+                    Ok(_)  => writeln!(self.writer, "Blocker of task \"{}\" changed", name.trim())?,
+                    Err(e) => writeln!(self.writer, "{}", e)?
+                }
+            },
+            "64" => {
+                let days: String = self.input("Enter number of days since completion: ")?;
+                match days.trim().parse::<i64>()
+                {
+                    Ok(days) if days >= 0 => {
+                        let count: usize = self.my_tasks.purge_completed_older_than(days);
+                        writeln!(self.writer, "{} completed tasks removed", count)?;
+                        if count > 0
+                        {
+                            self.record(format!("Removed {} tasks completed over {} days ago", count, days));
+                        }
+                    },
+                    _ => writeln!(self.writer, "Invalid number of days")?
+                }
+            },
+            "65" => {
+                self.compact = !self.compact;
+                writeln!(self.writer, "Compact list view {}", if self.compact { "on" } else { "off" })?;
+            },
+            "66" => {
+                let counts: BTreeMap<String, usize> = self.my_tasks.tag_counts();
+                if counts.is_empty()
+                {
+                    writeln!(self.writer, "No tagged tasks")?;
+                }
+                for (tag, count) in counts
+                {
+                    writeln!(self.writer, "{}: {}", tag, count)?;
+                }
+            },
+            "67" => {
+                let name: String = self.input("Enter name of task to time: ")?;
+                match self.my_tasks.start_timer(name.trim())
+                {
+                    Ok(stopped) => {
+                        if let Some((stopped, elapsed)) = stopped
+                        {
+                            writeln!(self.writer, "Timer of task \"{}\" stopped after {}", stopped, format_time_spent(elapsed))?;
+                        }
+                        writeln!(self.writer, "Timer of task \"{}\" started", name.trim())?;
+                    },
+                    Err(e) => writeln!(self.writer, "{}", e)?
+                }
+            },
+            "68" => {
+                match self.my_tasks.stop_timer()
+                {
+                    Some((name, elapsed)) => writeln!(self.writer, "Timer of task \"{}\" stopped after {}", name, format_time_spent(elapsed))?,
+                    None                  => writeln!(self.writer, "No timer is running")?
+                }
+            },
+            "69" => {
+                writeln!(self.writer, "{} overdue tasks moved to today", self.my_tasks.reschedule_overdue())?;
+            },
+            "70" => {
+                let name: String = self.input("Enter name of task to export: ")?;
+                let path: String = self.input("Enter path to JSON file: ")?;
+                match self.my_tasks.export_task(name.trim(), path.trim())
+                {
+                    Ok(_)  => writeln!(self.writer, "Task \"{}\" exported to \"{}\"", name.trim(), path.trim())?,
+                    Err(e) => writeln!(self.writer, "{}", e)?
+                }
+            },
+            "71" => {
+                let path: String = self.input("Enter path to JSON file: ")?;
+                match self.my_tasks.import_task(path.trim())
+                {
+                    Ok(name) => {
+                        writeln!(self.writer, "Task \"{}\" imported", name)?;
+                        self.record(format!("Added task \"{}\"", name));
+                    },
+                    Err(e) => writeln!(self.writer, "{}", e)?
+                }
+            },
+            "72" => {
+                let count: String = self.input_or_default("Enter number of tasks ", "5")?;
+                match count.parse::<usize>()
+                {
+                    Ok(count) => {
+                        for task in self.my_tasks.recent(count)
+                        {
+                            self.my_tasks.print_task(&mut self.writer, task, self.use_color)?;
+                            writeln!(self.writer)?;
+                        }
+                    },
+                    Err(_) => writeln!(self.writer, "Invalid number")?
+                }
+            },
+
+            _ => writeln!(self.writer, "Invalid input")?
+        }
+        return Ok(true);
+    }
+}
+
+/// Writes the command line help to `out`, stderr when it comes with an error
+fn print_usage(out: &mut dyn Write) -> io::Result<()>
+{
+    writeln!(out, "Usage:")?;
+    writeln!(out, "  taskmanager [--file <path>]                     start interactive mode")?;
+    writeln!(out, "  taskmanager add <name> [description] [priority] add a task to \"{}\"", DEFAULT_PATH)?;
+    writeln!(out, "  taskmanager list [--all]                        print unfinished (or all) tasks from \"{}\"", DEFAULT_PATH)?;
+    writeln!(out, "  taskmanager count [--priority <priority>]       print how many unfinished tasks (of at least that priority) there are")?;
+    writeln!(out, "  taskmanager --help                              show this message")?;
+    writeln!(out, "Commands accept --file <path> before them to use another file than \"{}\"", DEFAULT_PATH)?;
+    writeln!(out, "default_path in ~/{} and the {} environment variable set the file too, --file takes precedence", CONFIG_FILE, FILE_VAR)?;
+    Ok(())
+}
+
+/// Splits a leading `--file <path>` off the arguments
+fn parse_file_flag(mut args: Vec<String>) -> Result<(Option<String>, Vec<String>), TaskError>
+{
+    if args.first().map(|arg: &String| arg.as_str()) != Some("--file")
+    {
+        return Ok((None, args));
+    }
+    if args.len() < 2 || args[1].trim().is_empty()
+    {
+        return Err(TaskError::Invalid("Missing path after --file".to_string()));
+    }
+    let rest: Vec<String> = args.split_off(2);
+    return Ok((Some(args.swap_remove(1)), rest));
+}
+
+/// Runs a single non-interactive command on the file of `config`, writing its output to `out`
+/// with priorities colored if `use_color` is set, and returns the process exit code
+fn run_cli(args: Vec<String>, config: &AppConfig, out: &mut dyn Write, use_color: bool) -> i32
+{
+    let path: &str = &config.default_path;
+    let mut my_tasks: TaskManager = TaskManager::new();
+    my_tasks.max_tasks = config.max_tasks;
+    if Path::new(path).exists()
+    {
+        if let Err(e) = my_tasks.read_from_file(path)
+        {
+            eprintln!("{}", e);
+            return 1;
+        }
+    }
+
+    match args.first().map(|arg: &String| arg.as_str())
+    {
+        Some("add") => {
+            let name: String = match args.get(1)
+            {
+                Some(name) if !name.trim().is_empty() => name.trim().to_string(),
+                _ => {
+                    eprintln!("Missing task name");
+                    return 1;
+                }
+            };
+            let description: String = args.get(2).cloned().unwrap_or_default();
+            let priority: Priority = match args.get(3)
+            {
+                Some(priority) => match priority.parse()
+                {
+                    Ok(priority) => priority,
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        return 1;
+                    }
+                },
+                None => Priority::Medium
+            };
+
+            let task: Task = my_tasks.new_task(name, description, priority, None);
+            if let Err(e) = my_tasks.add(task)
+            {
+                eprintln!("{}", e);
+                return 1;
+            }
+            if let Err(e) = my_tasks.store_to_path(path, config.pretty_json)
+            {
+                eprintln!("{}", e);
+                return 1;
+            }
+            return 0;
+        },
+        Some("list") => {
+            let include_completed: bool = args.get(1).map(|arg: &String| arg.as_str()) == Some("--all");
+            my_tasks.sort(SortKey::Priority);
+            let _ = my_tasks.print(out, use_color, include_completed);
+            return 0;
+        },
+        Some("count") => {
+            let min: Priority = match (args.get(1).map(|arg: &String| arg.as_str()), args.get(2))
+            {
+                (None, _) => Priority::None,
+                (Some("--priority"), Some(priority)) => match priority.parse()
+                {
+                    Ok(priority) => priority,
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        return 1;
+                    }
+                },
+                _ => {
+                    let _ = print_usage(&mut io::stderr());
+                    return 1;
+                }
+            };
+            let count: usize = my_tasks.filter_by_priority(min)
+                .iter()
+                .filter(|task: &&&Task| !task.completed)
+                .count();
+            let _ = writeln!(out, "{}", count);
+            return 0;
+        },
+        Some("-h") | Some("--help") | Some("help") => {
+            let _ = print_usage(out);
+            return 0;
+        },
+        _ => {
+            let _ = print_usage(&mut io::stderr());
+            return 1;
+        }
+    }
+}
+
+fn main()
+{
+    let (file, args): (Option<String>, Vec<String>) = match parse_file_flag(std::env::args().skip(1).collect())
+    {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("{}", e);
+            let _ = print_usage(&mut io::stderr());
+            std::process::exit(1);
+        }
+    };
+    let mut config: AppConfig = AppConfig::load();
+    if let Some(path) = file
+    {
+        config.default_path = path;
+    }
+    if !args.is_empty()
+    {
+        let use_color: bool = config.use_color && io::stdout().is_terminal();
+        std::process::exit(run_cli(args, &config, &mut io::stdout(), use_color));
+    }
+
+    let mut console: ConsoleForTask = ConsoleForTask::with_config(config);
+    println!("Task Manager 1.0");
+    let _ = console.print_menu();
+
+    while console.process_input() {}
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use std::{
+        cell::RefCell,
+        rc::Rc
+    };
+
+    /// `hour` o'clock on a day of June 2026
+    fn at(day: u32, hour: u32) -> DateTime<Local>
+    {
+        return Local.with_ymd_and_hms(2026, 6, day, hour, 0, 0).unwrap();
+    }
+
+    fn task(name: &str, priority: Priority) -> Task
+    {
+        return Task::with_time(name.to_string(), String::new(), priority, at(1, 12));
+    }
+
+    fn manager(tasks: Vec<Task>) -> TaskManager
+    {
+        let mut manager: TaskManager = TaskManager::new();
+        for task in tasks
+        {
+            manager.push(task).unwrap();
+        }
+        return manager;
+    }
+
+    /// Path in the temp directory that no other test uses
+    fn temp_path(name: &str) -> String
+    {
+        let file: String = format!("taskmanager-{}-{}", std::process::id(), name);
+        return std::env::temp_dir().join(file).to_string_lossy().into_owned();
+    }
+
+    /// Deletes `path` along with the backup and temporary files saving may leave next to it
+    fn remove_files(path: &str)
+    {
+        for file in [path.to_string(), backup_path(path), format!("{}.tmp", path)]
+        {
+            let _ = std::fs::remove_file(file);
+        }
+    }
+
+    /// Writer that stays readable after the console took ownership of it
+    #[derive(Clone, Default)]
+    struct Output(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Output
+    {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize>
+        {
+            return self.0.borrow_mut().write(buf);
+        }
+
+        fn flush(&mut self) -> io::Result<()>
+        {
+            Ok(())
+        }
+    }
+
+    impl Output
+    {
+        fn text(&self) -> String
+        {
+            return String::from_utf8_lossy(&self.0.borrow()).into_owned();
+        }
+    }
+
+    /// Console on `path` that reads `input` as typed commands
+    fn console(path: &str, input: &str) -> (ConsoleForTask, Output)
+    {
+        let output: Output = Output::default();
+        let reader: Box<dyn BufRead> = Box::new(io::Cursor::new(input.as_bytes().to_vec()));
+        return (ConsoleForTask::with_io(path.to_string(), reader, Box::new(output.clone())), output);
+    }
+
+    /// Typed input that adds a task with every optional field left empty
+    fn add_input(name: &str) -> String
+    {
+        return format!("1\n{}\n.\n\n\n\n\n\n\n", name);
+    }
+
+    fn run(console: &mut ConsoleForTask)
+    {
+        while console.process_input() {}
+    }
+
+    /// Config that points batch mode at `path`
+    fn cli_config(path: &str) -> AppConfig
+    {
+        return AppConfig { default_path: path.to_string(), ..AppConfig::default() };
+    }
+
+    fn args(args: &[&str]) -> Vec<String>
+    {
+        return args.iter().map(|arg: &&str| arg.to_string()).collect();
+    }
+
+    /// Held by tests that change environment variables, which all tests share
+    static ENV_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    fn names<'a>(tasks: impl IntoIterator<Item = &'a Task>) -> Vec<&'a str>
+    {
+        return tasks.into_iter().map(|task: &Task| task.name.as_str()).collect();
+    }
+
+    #[test]
+    fn sort_puts_highest_priority_first()
+    {
+        let mut manager: TaskManager = manager(vec![
+            task("low", Priority::Low),
+            task("very high", Priority::VeryHigh),
+            task("none", Priority::None),
+            task("medium", Priority::Medium),
+            task("high", Priority::High)
+        ]);
+        manager.sort(SortKey::Priority);
+        assert_eq!(names(&manager.tasks), ["very high", "high", "medium", "low", "none"]);
+    }
+
+    #[test]
+    fn sort_puts_older_task_first_within_a_priority()
+    {
+        let mut newer: Task = task("newer", Priority::High);
+        newer.add_time = at(2, 12);
+        let older: Task = Task::with_time("older".to_string(), String::new(), Priority::High, at(1, 9));
+        let mut manager: TaskManager = manager(vec![newer, older]);
+        manager.sort(SortKey::Priority);
+
+        let output: String = manager.to_display_string(false, true);
+        assert!(output.find("older").unwrap() < output.find("newer").unwrap());
+    }
+
+    #[test]
+    fn sort_by_name_ignores_case()
+    {
+        let mut manager: TaskManager = manager(vec![
+            task("banana", Priority::High),
+            task("Cherry", Priority::Low),
+            task("apple", Priority::None)
+        ]);
+        manager.sort(SortKey::Name);
+        assert_eq!(names(&manager.tasks), ["apple", "banana", "Cherry"]);
+    }
+
+    #[test]
+    fn store_to_file_overwrites_an_existing_file()
+    {
+        let path: String = temp_path("overwrite.json");
+        let mut manager: TaskManager = manager(vec![task("first", Priority::Low)]);
+        manager.store_to_file(&path, false).unwrap();
+        manager.push(task("second", Priority::High)).unwrap();
+        manager.store_to_file(&path, false).unwrap();
+
+        let mut loaded: TaskManager = TaskManager::new();
+        loaded.read_from_file(&path).unwrap();
+        remove_files(&path);
+        assert_eq!(names(&loaded.tasks), ["first", "second"]);
+    }
+
+    #[test]
+    fn toggle_complete_flips_completion()
+    {
+        let mut manager: TaskManager = manager(vec![task("laundry", Priority::Low)]);
+        manager.toggle_complete("laundry").unwrap();
+        assert!(manager.tasks[0].completed);
+        assert!(manager.tasks[0].to_display_string(false, at(1, 12), false).contains("[x] laundry"));
+        manager.toggle_complete("laundry").unwrap();
+        assert!(!manager.tasks[0].completed);
+        assert_eq!(manager.toggle_complete("dishes"), Err(TaskError::NotFound("dishes".to_string())));
+    }
+
+    #[test]
+    fn completed_defaults_to_false_when_missing()
+    {
+        let json: &str = r#"{"name": "old", "description": "", "priority": "Low", "add_time": "2026-06-01T12:00:00+00:00"}"#;
+        let task: Task = serde_json::from_str(json).unwrap();
+        assert!(!task.completed);
+    }
+
+    #[test]
+    fn overdue_marks_only_unfinished_tasks_past_their_due_date()
+    {
+        let mut late: Task = task("late", Priority::Low);
+        late.due_date = Some(at(5, 12));
+        let mut done: Task = task("done", Priority::Low);
+        done.due_date = Some(at(5, 12));
+        done.completed = true;
+        let mut upcoming: Task = task("upcoming", Priority::Low);
+        upcoming.due_date = Some(at(20, 12));
+        let mut manager: TaskManager = manager(vec![late, done, upcoming]);
+        manager.clock = Box::new(FixedClock(at(10, 12)));
+
+        assert_eq!(names(manager.overdue()), ["late"]);
+        assert!(manager.tasks[0].to_display_string(false, at(10, 12), false).contains("OVERDUE"));
+        assert!(!manager.tasks[2].to_display_string(false, at(10, 12), false).contains("OVERDUE"));
+    }
+
+    #[test]
+    fn edit_keeps_add_time()
+    {
+        let mut manager: TaskManager = manager(vec![task("draft", Priority::Low)]);
+        manager.edit("draft", Some("final".to_string()), Some("ready".to_string()), Some(Priority::High)).unwrap();
+
+        let edited: &Task = &manager.tasks[0];
+        assert_eq!(edited.name, "final");
+        assert_eq!(edited.description, "ready");
+        assert_eq!(edited.priority, Priority::High);
+        assert_eq!(edited.add_time, at(1, 12));
+        assert_eq!(manager.edit("draft", None, None, None), Err(TaskError::NotFound("draft".to_string())));
+    }
+
+    #[test]
+    fn ids_stay_unique_across_save_and_load()
+    {
+        let path: String = temp_path("ids.json");
+        let mut manager: TaskManager = manager(vec![task("a", Priority::Low), task("b", Priority::Low)]);
+        manager.remove("b").unwrap();
+        manager.store_to_file(&path, false).unwrap();
+
+        let mut loaded: TaskManager = TaskManager::new();
+        loaded.read_from_file(&path).unwrap();
+        remove_files(&path);
+        loaded.push(task("c", Priority::Low)).unwrap();
+
+        // The id of the removed task is not handed out again either
+        let ids: Vec<u64> = loaded.tasks.iter().map(|task: &Task| task.id).collect();
+        assert_eq!(ids, [1, 3]);
+        assert_eq!(loaded.find_by_id(3), Some(1));
+    }
+
+    #[test]
+    fn read_from_file_fails_for_a_missing_path()
+    {
+        let path: String = temp_path("missing.json");
+        let mut manager: TaskManager = manager(vec![task("kept", Priority::Low)]);
+        assert!(manager.read_from_file(&path).is_err());
+        assert_eq!(names(&manager.tasks), ["kept"]);
+    }
+
+    #[test]
+    fn removing_a_missing_task_keeps_the_session_running()
+    {
+        let path: String = temp_path("remove-missing.json");
+        let (mut console, output): (ConsoleForTask, Output) = console(&path, "3\nghost\n5\n9\n");
+        assert!(console.process_input());
+        assert!(console.process_input());
+        assert!(!console.process_input());
+        remove_files(&path);
+
+        let text: String = output.text();
+        assert!(text.contains("Task ghost not found"));
+        assert!(text.contains("No tasks yet."));
+    }
+
+    #[test]
+    fn search_matches_names_and_descriptions()
+    {
+        let mut report: Task = task("Write report", Priority::High);
+        report.description = "quarterly numbers".to_string();
+        let mut call: Task = task("Call Bob", Priority::Low);
+        call.description = "about the REPORT deadline".to_string();
+        let manager: TaskManager = manager(vec![report, call, task("Groceries", Priority::Low)]);
+
+        assert_eq!(names(manager.search("report")), ["Write report", "Call Bob"]);
+        assert_eq!(names(manager.search("quarterly")), ["Write report"]);
+        assert!(manager.search("holiday").is_empty());
+    }
+
+    #[test]
+    fn filter_by_priority_keeps_tasks_at_or_above_the_minimum()
+    {
+        let manager: TaskManager = manager(vec![
+            task("none", Priority::None),
+            task("low", Priority::Low),
+            task("medium", Priority::Medium),
+            task("high", Priority::High),
+            task("very high", Priority::VeryHigh)
+        ]);
+        assert_eq!(names(manager.filter_by_priority(Priority::High)), ["high", "very high"]);
+    }
+
+    #[test]
+    fn export_csv_quotes_fields_with_commas()
+    {
+        let path: String = temp_path("export.csv");
+        let mut shopping: Task = task("Shopping", Priority::Medium);
+        shopping.description = "milk, eggs".to_string();
+        let mut quote: Task = task("Quote", Priority::Low);
+        quote.description = "say \"hi\"".to_string();
+        let manager: TaskManager = manager(vec![shopping, quote]);
+        manager.export_csv(&path).unwrap();
+
+        let csv: String = std::fs::read_to_string(&path).unwrap();
+        remove_files(&path);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "name,description,priority,add_time,completed");
+        assert!(lines[1].starts_with("Shopping,\"milk, eggs\",Medium,"));
+        assert!(lines[2].starts_with("Quote,\"say \"\"hi\"\"\",Low,"));
+        assert_eq!(lines.len(), 3);
+    }
+
+    #[test]
+    fn csv_export_and_import_round_trip()
+    {
+        let path: String = temp_path("round-trip.csv");
+        let mut shopping: Task = task("Shopping, weekly", Priority::VeryHigh);
+        shopping.description = "milk, \"good\" eggs\nand bread".to_string();
+        shopping.completed = true;
+        let original: TaskManager = manager(vec![shopping, task("Rest", Priority::None)]);
+        original.export_csv(&path).unwrap();
+
+        let mut imported: TaskManager = TaskManager::new();
+        let count: usize = imported.import_csv(&path).unwrap();
+        remove_files(&path);
+        assert_eq!(count, 2);
+        for (before, after) in original.tasks.iter().zip(imported.tasks.iter())
+        {
+            assert_eq!(before.name, after.name);
+            assert_eq!(before.description, after.description);
+            assert_eq!(before.priority, after.priority);
+            assert_eq!(before.add_time, after.add_time);
+            assert_eq!(before.completed, after.completed);
+        }
+    }
+
+    #[test]
+    fn import_csv_maps_unknown_priorities_to_none()
+    {
+        let path: String = temp_path("unknown-priority.csv");
+        std::fs::write(&path, "name,description,priority,add_time,completed\nOdd,,Urgent,2026-06-01T12:00:00+00:00,false\n").unwrap();
+        let mut manager: TaskManager = TaskManager::new();
+        manager.import_csv(&path).unwrap();
+        remove_files(&path);
+        assert_eq!(manager.tasks[0].priority, Priority::None);
+    }
+
+    #[test]
+    fn priority_parses_indices_and_names_in_any_case()
+    {
+        let spellings: [(&str, Priority); 12] = [
+            ("0", Priority::None),
+            ("none", Priority::None),
+            ("1", Priority::Low),
+            ("low", Priority::Low),
+            ("2", Priority::Medium),
+            ("Medium", Priority::Medium),
+            ("3", Priority::High),
+            ("HIGH", Priority::High),
+            ("4", Priority::VeryHigh),
+            ("very high", Priority::VeryHigh),
+            ("Very High", Priority::VeryHigh),
+            (" low\n", Priority::Low)
+        ];
+        for (input, expected) in spellings
+        {
+            assert_eq!(input.parse::<Priority>(), Ok(expected), "parsing {:?}", input);
+        }
+    }
+
+    #[test]
+    fn priority_rejects_unknown_text()
+    {
+        assert!(matches!("urgent".parse::<Priority>(), Err(TaskError::Parse(_))));
+        assert!("5".parse::<Priority>().is_err());
+        assert!("".parse::<Priority>().is_err());
+    }
+
+    #[test]
+    fn stats_counts_every_field()
+    {
+        let mut done: Task = task("done", Priority::High);
+        done.completed = true;
+        let mut late: Task = task("late", Priority::VeryHigh);
+        late.due_date = Some(at(2, 12));
+        let mut manager: TaskManager = manager(vec![
+            done,
+            late,
+            task("high", Priority::High),
+            task("medium", Priority::Medium),
+            task("none", Priority::None)
+        ]);
+        manager.clock = Box::new(FixedClock(at(10, 12)));
+
+        let stats: TaskStats = manager.stats();
+        assert_eq!(stats.total, 5);
+        assert_eq!(stats.very_high, 1);
+        assert_eq!(stats.high, 2);
+        assert_eq!(stats.medium, 1);
+        assert_eq!(stats.low, 0);
+        assert_eq!(stats.none, 1);
+        assert_eq!(stats.completed, 1);
+        assert_eq!(stats.overdue, 1);
+    }
+
+    #[test]
+    fn undo_brings_back_a_removed_task()
+    {
+        let mut manager: TaskManager = manager(vec![task("a", Priority::Low), task("b", Priority::Low)]);
+        manager.remove("a").unwrap();
+        assert_eq!(names(&manager.tasks), ["b"]);
+        manager.undo().unwrap();
+        assert_eq!(names(&manager.tasks), ["a", "b"]);
+        assert_eq!(manager.undo(), Err(TaskError::Invalid("Nothing to undo".to_string())));
+    }
+
+    #[test]
+    fn undo_brings_back_a_cleared_list()
+    {
+        let mut manager: TaskManager = manager(vec![task("a", Priority::Low), task("b", Priority::Low)]);
+        manager.clear();
+        assert!(manager.tasks.is_empty());
+        manager.undo().unwrap();
+        assert_eq!(names(&manager.tasks), ["a", "b"]);
+    }
+
+    #[test]
+    fn undo_keeps_at_most_the_undo_limit()
+    {
+        let mut manager: TaskManager = manager((0..UNDO_LIMIT + 2).map(|i: usize| task(&i.to_string(), Priority::Low)).collect());
+        while manager.pop().is_some() {}
+        let mut undone: usize = 0;
+        while manager.undo().is_ok()
+        {
+            undone += 1;
+        }
+        assert_eq!(undone, UNDO_LIMIT);
+    }
+
+    #[test]
+    fn undo_does_not_cross_a_file_load()
+    {
+        let path: String = temp_path("undo-load.json");
+        manager(vec![task("saved", Priority::Low)]).store_to_file(&path, false).unwrap();
+        let mut manager: TaskManager = manager(vec![task("a", Priority::Low), task("b", Priority::Low)]);
+        manager.remove("a").unwrap();
+        manager.read_from_file(&path).unwrap();
+        remove_files(&path);
+
+        assert!(manager.undo().is_err());
+        assert_eq!(names(&manager.tasks), ["saved"]);
+    }
+
+    #[test]
+    fn exit_saves_the_tasks_to_the_console_path()
+    {
+        let path: String = temp_path("exit.json");
+        let input: String = format!("{}{}9\n", add_input("first"), add_input("second"));
+        let (mut console, output): (ConsoleForTask, Output) = console(&path, &input);
+        run(&mut console);
+
+        let mut saved: TaskManager = TaskManager::new();
+        saved.read_from_file(&path).unwrap();
+        remove_files(&path);
+        assert_eq!(names(&saved.tasks), ["first", "second"]);
+        assert!(output.text().contains(&format!("Tasks saved to \"{}\"", path)));
+    }
+
+    #[test]
+    fn console_loads_its_path_on_startup()
+    {
+        let path: String = temp_path("startup.json");
+        manager(vec![task("stored", Priority::Low)]).store_to_file(&path, false).unwrap();
+        let (console, _): (ConsoleForTask, Output) = console(&path, "");
+        remove_files(&path);
+        assert_eq!(names(&console.my_tasks.tasks), ["stored"]);
+    }
+
+    #[test]
+    fn a_file_that_failed_to_load_is_not_saved_over_on_exit()
+    {
+        let path: String = temp_path("unreadable.json");
+        std::fs::write(&path, "{\"version\": 99, \"tasks\": []}").unwrap();
+        let (mut console, output): (ConsoleForTask, Output) = console(&path, &format!("{}9\n", add_input("new")));
+        run(&mut console);
+
+        let content: String = std::fs::read_to_string(&path).unwrap();
+        let backed_up: bool = Path::new(&backup_path(&path)).exists();
+        remove_files(&path);
+        assert_eq!(content, "{\"version\": 99, \"tasks\": []}");
+        assert!(!backed_up);
+        assert!(output.text().contains(&format!("Tasks not saved, \"{}\" could not be loaded on startup", path)));
+    }
+
+    #[test]
+    fn storing_over_a_file_that_failed_to_load_saves_on_exit_again()
+    {
+        let path: String = temp_path("unreadable-stored.json");
+        std::fs::write(&path, "not tasks").unwrap();
+        let (mut console, _): (ConsoleForTask, Output) = console(&path, &format!("{}7\n\n{}9\n", add_input("a"), add_input("b")));
+        run(&mut console);
+
+        let mut saved: TaskManager = TaskManager::new();
+        saved.read_from_file(&path).unwrap();
+        remove_files(&path);
+        assert_eq!(names(&saved.tasks), ["a", "b"]);
+    }
+
+    #[test]
+    fn store_command_uses_the_default_path_on_empty_input()
+    {
+        let path: String = temp_path("default-path.json");
+        let (mut console, output): (ConsoleForTask, Output) = console(&path, &format!("{}7\n\n", add_input("a")));
+        assert!(console.process_input());
+        assert!(console.process_input());
+
+        let stored: bool = Path::new(&path).exists();
+        remove_files(&path);
+        assert!(stored);
+        assert!(output.text().contains(&format!("[{}]", path)));
+    }
+
+    #[test]
+    fn new_console_uses_the_default_path()
+    {
+        assert_eq!(ConsoleForTask::new().path, DEFAULT_PATH);
+    }
+
+    #[test]
+    fn listing_header_counts_the_tasks()
+    {
+        let manager: TaskManager = manager(vec![task("a", Priority::Low), task("b", Priority::Low), task("c", Priority::Low)]);
+        assert!(manager.to_display_string(false, true).starts_with("Tasks (3):\n"));
+        assert_eq!(TaskManager::new().to_display_string(false, true), "No tasks yet.\n");
+    }
+
+    #[test]
+    fn task_display_string_shows_every_set_field()
+    {
+        let mut write: Task = task("Write", Priority::High);
+        write.description = "first line\nsecond line".to_string();
+        let manager: TaskManager = manager(vec![write, task("Plain", Priority::None)]);
+
+        assert_eq!(
+            manager.tasks[0].to_display_string(false, at(1, 12), false),
+            "#1 [ ] Write [0%] | High | 01-06-2026  12:00:00\n\"first line\n second line\""
+        );
+        assert_eq!(manager.tasks[1].to_display_string(false, at(1, 12), false), "#2 [ ] Plain [0%] |  | 01-06-2026  12:00:00\n\"\"");
+    }
+
+    #[test]
+    fn merge_from_file_appends_to_the_current_tasks()
+    {
+        let path: String = temp_path("merge.json");
+        manager(vec![task("c", Priority::Low), task("d", Priority::Low)]).store_to_file(&path, false).unwrap();
+        let mut manager: TaskManager = manager(vec![task("a", Priority::Low), task("b", Priority::Low)]);
+        let count: usize = manager.merge_from_file(&path).unwrap();
+        remove_files(&path);
+
+        assert_eq!(count, 2);
+        assert_eq!(names(&manager.tasks), ["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn parse_tags_splits_on_commas_and_drops_empty_tags()
+    {
+        assert_eq!(Task::parse_tags(" work, ,Home ,urgent,"), ["work", "Home", "urgent"]);
+        assert!(Task::parse_tags("").is_empty());
+    }
+
+    #[test]
+    fn filter_by_tag_ignores_case()
+    {
+        let mut report: Task = task("report", Priority::Low);
+        report.tags = vec!["Work".to_string(), "urgent".to_string()];
+        let mut garden: Task = task("garden", Priority::Low);
+        garden.tags = vec!["home".to_string()];
+        let manager: TaskManager = manager(vec![report, garden, task("untagged", Priority::Low)]);
+
+        assert_eq!(names(manager.filter_by_tag("work")), ["report"]);
+        assert_eq!(names(manager.filter_by_tag("HOME")), ["garden"]);
+        assert!(manager.filter_by_tag("work ").is_empty());
+    }
+
+    #[test]
+    fn add_rejects_a_name_that_is_taken()
+    {
+        let mut manager: TaskManager = TaskManager::new();
+        manager.add(task("Report", Priority::Low)).unwrap();
+        assert_eq!(manager.add(task("report", Priority::High)), Err(TaskError::Duplicate("report".to_string())));
+        assert_eq!(manager.tasks.len(), 1);
+    }
+
+    #[test]
+    fn edit_rejects_a_name_that_is_taken()
+    {
+        let mut manager: TaskManager = manager(vec![task("a", Priority::Low), task("b", Priority::Low)]);
+        assert_eq!(manager.edit("b", Some("A".to_string()), None, None), Err(TaskError::Duplicate("A".to_string())));
+        assert_eq!(names(&manager.tasks), ["a", "b"]);
+        manager.edit("b", Some("B".to_string()), None, None).unwrap();
+        assert_eq!(names(&manager.tasks), ["a", "B"]);
+    }
+
+    #[test]
+    fn remove_completed_keeps_only_unfinished_tasks()
+    {
+        let mut manager: TaskManager = manager(vec![task("a", Priority::Low), task("b", Priority::Low), task("c", Priority::Low)]);
+        manager.tasks[0].completed = true;
+        manager.tasks[2].completed = true;
+
+        assert_eq!(manager.remove_completed(), 2);
+        assert_eq!(names(&manager.tasks), ["b"]);
+        assert_eq!(manager.remove_completed(), 0);
+    }
+
+    #[test]
+    fn page_slice_returns_the_tasks_of_the_page()
+    {
+        let manager: TaskManager = manager((1..=7).map(|i: usize| task(&i.to_string(), Priority::Low)).collect());
+        assert_eq!(manager.page_count(3), 3);
+        assert_eq!(names(manager.page_slice(1, 3)), ["1", "2", "3"]);
+        assert_eq!(names(manager.page_slice(3, 3)), ["7"]);
+        // Out of range pages are clamped
+        assert_eq!(names(manager.page_slice(0, 3)), ["1", "2", "3"]);
+        assert_eq!(names(manager.page_slice(9, 3)), ["7"]);
+        assert!(TaskManager::new().page_slice(1, 3).is_empty());
+    }
+
+    #[test]
+    fn display_without_color_has_no_escape_codes()
+    {
+        let mut manager: TaskManager = TaskManager::new();
+        for priority in [Priority::None, Priority::Low, Priority::Medium, Priority::High, Priority::VeryHigh]
+        {
+            manager.push(task(&format!("{:?}", priority), priority)).unwrap();
+        }
+        assert!(!manager.to_display_string(false, true).contains('\x1b'));
+        assert!(manager.tasks[4].to_display_string(true, at(1, 12), false).contains("\x1b[31mVery High\x1b[0m"));
+    }
+
+    #[test]
+    fn move_up_and_down_swap_with_the_neighbour()
+    {
+        let mut manager: TaskManager = manager(vec![task("a", Priority::Low), task("b", Priority::Low), task("c", Priority::Low)]);
+        manager.move_up("c").unwrap();
+        assert_eq!(names(&manager.tasks), ["a", "c", "b"]);
+        manager.move_down("a").unwrap();
+        assert_eq!(names(&manager.tasks), ["c", "a", "b"]);
+    }
+
+    #[test]
+    fn moving_past_either_end_fails()
+    {
+        let mut manager: TaskManager = manager(vec![task("a", Priority::Low), task("b", Priority::Low)]);
+        assert!(matches!(manager.move_up("a"), Err(TaskError::Invalid(_))));
+        assert!(matches!(manager.move_down("b"), Err(TaskError::Invalid(_))));
+        assert_eq!(manager.move_up("z"), Err(TaskError::NotFound("z".to_string())));
+        assert_eq!(names(&manager.tasks), ["a", "b"]);
+    }
+
+    #[test]
+    fn yaml_store_and_read_round_trip()
+    {
+        let path: String = temp_path("round-trip.yaml");
+        let mut report: Task = task("report", Priority::VeryHigh);
+        report.description = "numbers: 1, 2".to_string();
+        report.due_date = Some(at(3, 9));
+        report.tags = vec!["work".to_string()];
+        report.completed = true;
+        let original: TaskManager = manager(vec![report]);
+        original.store_to_yaml(&path).unwrap();
+
+        let mut loaded: TaskManager = TaskManager::new();
+        loaded.read_from_file(&path).unwrap();
+        remove_files(&path);
+        let (before, after): (&Task, &Task) = (&original.tasks[0], &loaded.tasks[0]);
+        assert_eq!(after.id, before.id);
+        assert_eq!(after.name, before.name);
+        assert_eq!(after.description, before.description);
+        assert_eq!(after.priority, before.priority);
+        assert_eq!(after.add_time, before.add_time);
+        assert_eq!(after.due_date, before.due_date);
+        assert_eq!(after.tags, before.tags);
+        assert_eq!(after.completed, before.completed);
+    }
+
+    #[test]
+    fn yaml_paths_are_saved_as_yaml_on_exit()
+    {
+        let path: String = temp_path("exit.yaml");
+        let (mut console, _): (ConsoleForTask, Output) = console(&path, &format!("{}9\n", add_input("a")));
+        run(&mut console);
+
+        let content: String = std::fs::read_to_string(&path).unwrap();
+        remove_files(&path);
+        assert!(serde_json::from_str::<serde_json::Value>(&content).is_err());
+        assert!(content.contains("name: a"));
+    }
+
+    #[test]
+    fn read_from_file_reads_json_and_yaml_whatever_the_extension()
+    {
+        let json_path: String = temp_path("json-content.txt");
+        let yaml_path: String = temp_path("yaml-content.txt");
+        manager(vec![task("from json", Priority::Low)]).store_to_file(&json_path, true).unwrap();
+        manager(vec![task("from yaml", Priority::Low)]).store_to_yaml(&yaml_path).unwrap();
+
+        let mut manager: TaskManager = TaskManager::new();
+        manager.read_from_file(&json_path).unwrap();
+        assert_eq!(names(&manager.tasks), ["from json"]);
+        manager.read_from_file(&yaml_path).unwrap();
+        assert_eq!(names(&manager.tasks), ["from yaml"]);
+        remove_files(&json_path);
+        remove_files(&yaml_path);
+    }
+
+    #[test]
+    fn read_from_file_fails_for_content_in_neither_format()
+    {
+        let path: String = temp_path("garbage.txt");
+        std::fs::write(&path, "[not: valid").unwrap();
+        let result: Result<(), TaskError> = TaskManager::new().read_from_file(&path);
+        remove_files(&path);
+        assert!(matches!(result, Err(TaskError::Parse(_))));
+    }
+
+    #[test]
+    fn run_cli_adds_a_task_to_the_file()
+    {
+        let path: String = temp_path("cli-add.json");
+        let config: AppConfig = cli_config(&path);
+        assert_eq!(run_cli(args(&["add", "Report", "numbers", "high"]), &config, &mut io::sink(), false), 0);
+        assert_eq!(run_cli(args(&["add", "Call"]), &config, &mut io::sink(), false), 0);
+        assert_eq!(run_cli(args(&["list", "--all"]), &config, &mut io::sink(), false), 0);
+
+        let mut saved: TaskManager = TaskManager::new();
+        saved.read_from_file(&path).unwrap();
+        remove_files(&path);
+        assert_eq!(names(&saved.tasks), ["Report", "Call"]);
+        assert_eq!(saved.tasks[0].description, "numbers");
+        assert_eq!(saved.tasks[0].priority, Priority::High);
+        assert_eq!(saved.tasks[1].priority, Priority::Medium);
+    }
+
+    #[test]
+    fn run_cli_fails_for_bad_arguments()
+    {
+        let path: String = temp_path("cli-bad.json");
+        let config: AppConfig = cli_config(&path);
+        assert_eq!(run_cli(args(&["add"]), &config, &mut io::sink(), false), 1);
+        assert_eq!(run_cli(args(&["add", "Report", "", "urgent"]), &config, &mut io::sink(), false), 1);
+        assert_eq!(run_cli(args(&["frobnicate"]), &config, &mut io::sink(), false), 1);
+        assert_eq!(run_cli(args(&["--help"]), &config, &mut io::sink(), false), 0);
+        assert!(!Path::new(&path).exists());
+    }
+
+    #[test]
+    fn run_cli_refuses_a_duplicate_name()
+    {
+        let path: String = temp_path("cli-duplicate.json");
+        let config: AppConfig = cli_config(&path);
+        assert_eq!(run_cli(args(&["add", "Report"]), &config, &mut io::sink(), false), 0);
+        assert_eq!(run_cli(args(&["add", "report"]), &config, &mut io::sink(), false), 1);
+        remove_files(&path);
+    }
+
+    #[test]
+    fn read_multiline_joins_lines_up_to_the_terminator()
+    {
+        let mut reader: io::Cursor<&[u8]> = io::Cursor::new(b"first line\nsecond line\r\n.\nnext command\n");
+        assert_eq!(ConsoleForTask::read_multiline(&mut reader).unwrap(), "first line\nsecond line");
+
+        // The terminator is consumed, the rest is left for the next prompt
+        let mut rest: String = String::new();
+        reader.read_line(&mut rest).unwrap();
+        assert_eq!(rest, "next command\n");
+    }
+
+    #[test]
+    fn read_multiline_stops_at_the_end_of_input()
+    {
+        let mut reader: io::Cursor<&[u8]> = io::Cursor::new(b"only line\n");
+        assert_eq!(ConsoleForTask::read_multiline(&mut reader).unwrap(), "only line");
+        let mut empty: io::Cursor<&[u8]> = io::Cursor::new(b" . \n");
+        assert_eq!(ConsoleForTask::read_multiline(&mut empty).unwrap(), "");
+    }
+
+    #[test]
+    fn completing_a_daily_task_schedules_the_next_one()
+    {
+        let mut water: Task = task("Water", Priority::Low);
+        water.recurrence = Some(Recurrence::Daily);
+        water.due_date = Some(at(2, 9));
+        water.progress = 50;
+        water.time_spent = Duration::minutes(20);
+        water.subtasks = vec![SubTask { title: "fill can".to_string(), done: true }];
+        let mut manager: TaskManager = manager(vec![water]);
+        manager.clock = Box::new(FixedClock(at(2, 8)));
+        manager.toggle_complete("Water").unwrap();
+
+        assert_eq!(names(&manager.tasks), ["Water", "Water (2)"]);
+        assert!(manager.tasks[0].completed);
+        let next: &Task = &manager.tasks[1];
+        assert!(!next.completed);
+        assert_eq!(next.add_time, at(2, 8));
+        assert_eq!(next.due_date, Some(at(3, 9)));
+        assert_eq!(next.recurrence, Some(Recurrence::Daily));
+        assert_eq!(next.progress, 0);
+        assert_eq!(next.time_spent, Duration::zero());
+        assert!(!next.subtasks[0].done);
+        assert_ne!(next.id, manager.tasks[0].id);
+    }
+
+    #[test]
+    fn each_occurrence_can_be_completed_by_name()
+    {
+        let mut water: Task = task("Water", Priority::Low);
+        water.recurrence = Some(Recurrence::Weekly);
+        water.due_date = Some(at(1, 9));
+        let mut manager: TaskManager = manager(vec![water]);
+        manager.complete("Water").unwrap();
+        manager.complete("Water (2)").unwrap();
+
+        assert_eq!(names(&manager.tasks), ["Water", "Water (2)", "Water (3)"]);
+        assert!(manager.tasks[1].completed);
+        assert_eq!(manager.tasks[2].due_date, Some(at(15, 9)));
+        assert_eq!(manager.incomplete().len(), 1);
+    }
+
+    #[test]
+    fn parse_progress_rejects_values_outside_0_to_100()
+    {
+        assert_eq!(Task::parse_progress("45"), Ok(45));
+        assert_eq!(Task::parse_progress(" 100%\n"), Ok(100));
+        assert_eq!(Task::parse_progress("0"), Ok(0));
+        for input in ["101", "-1", "half", ""]
+        {
+            assert!(matches!(Task::parse_progress(input), Err(TaskError::Parse(_))), "parsing {:?}", input);
+        }
+    }
+
+    #[test]
+    fn average_progress_is_the_mean_over_all_tasks()
+    {
+        let mut manager: TaskManager = manager(vec![task("a", Priority::Low), task("b", Priority::Low), task("c", Priority::Low)]);
+        manager.tasks[0].progress = 100;
+        manager.tasks[1].progress = 50;
+        assert_eq!(manager.average_progress(), 50.0);
+        assert_eq!(TaskManager::new().average_progress(), 0.0);
+    }
+
+    #[test]
+    fn subtasks_count_towards_the_ratio()
+    {
+        let mut manager: TaskManager = manager(vec![task("move", Priority::High)]);
+        assert_eq!(manager.tasks[0].subtask_progress(), None);
+        manager.add_subtask("move", "pack".to_string()).unwrap();
+        manager.add_subtask("move", "drive".to_string()).unwrap();
+        manager.toggle_subtask("move", 1).unwrap();
+
+        assert_eq!(manager.tasks[0].subtask_progress(), Some((1, 2)));
+        assert!(manager.tasks[0].to_display_string(false, at(1, 12), false).contains("move (1/2)"));
+        assert!(matches!(manager.toggle_subtask("move", 3), Err(TaskError::Invalid(_))));
+        assert!(matches!(manager.toggle_subtask("move", 0), Err(TaskError::Invalid(_))));
+        assert_eq!(manager.add_subtask("stay", "rest".to_string()), Err(TaskError::NotFound("stay".to_string())));
+    }
+
+    #[test]
+    fn subtasks_survive_serialization()
+    {
+        let mut manager: TaskManager = manager(vec![task("move", Priority::High)]);
+        manager.add_subtask("move", "pack".to_string()).unwrap();
+        manager.toggle_subtask("move", 1).unwrap();
+
+        let json: String = serde_json::to_string(&manager.tasks[0]).unwrap();
+        let task: Task = serde_json::from_str(&json).unwrap();
+        assert_eq!(task.subtasks.len(), 1);
+        assert_eq!(task.subtasks[0].title, "pack");
+        assert!(task.subtasks[0].done);
+    }
+
+    #[test]
+    fn remove_of_a_missing_task_is_not_found()
+    {
+        let mut manager: TaskManager = manager(vec![task("a", Priority::Low)]);
+        match manager.remove("b")
+        {
+            Err(TaskError::NotFound(name)) => assert_eq!(name, "b"),
+            _ => panic!("expected NotFound")
+        }
+        assert_eq!(TaskError::NotFound("b".to_string()).to_string(), "Task b not found");
+    }
+
+    #[test]
+    fn read_from_file_tells_io_and_parse_errors_apart()
+    {
+        let path: String = temp_path("broken.json");
+        assert!(matches!(TaskManager::new().read_from_file(&path), Err(TaskError::Io(_))));
+        std::fs::write(&path, "{ broken").unwrap();
+        let result: Result<(), TaskError> = TaskManager::new().read_from_file(&path);
+        remove_files(&path);
+        assert!(matches!(result, Err(TaskError::Parse(_))));
+    }
+
+    #[test]
+    fn case_sensitive_names_keep_api_and_api_apart()
+    {
+        let mut manager: TaskManager = TaskManager::new();
+        manager.match_case = true;
+        manager.add(task("API", Priority::High)).unwrap();
+        manager.add(task("api", Priority::Low)).unwrap();
+
+        assert_eq!(manager.find("api"), Some(1));
+        assert_eq!(manager.find("Api"), None);
+        manager.remove("API").unwrap();
+        assert_eq!(names(&manager.tasks), ["api"]);
+    }
+
+    #[test]
+    fn names_ignore_case_by_default()
+    {
+        let mut manager: TaskManager = TaskManager::new();
+        manager.add(task("API", Priority::High)).unwrap();
+        assert!(manager.add(task("api", Priority::Low)).is_err());
+        assert_eq!(manager.find("Api"), Some(0));
+    }
+
+    #[test]
+    fn confirm_accepts_only_yes()
+    {
+        let answers: [(&str, bool); 7] = [
+            ("y\n", true),
+            ("YES\n", true),
+            (" yes \n", true),
+            ("n\n", false),
+            ("no\n", false),
+            ("yep, sure\n", false),
+            ("", false)
+        ];
+        for (input, expected) in answers
+        {
+            let (mut console, _): (ConsoleForTask, Output) = console(&temp_path("confirm.json"), input);
+            assert_eq!(console.confirm("Sure?"), expected, "answering {:?}", input);
+        }
+    }
+
+    #[test]
+    fn clear_command_needs_a_confirmation()
+    {
+        let path: String = temp_path("clear.json");
+        let (mut console, _): (ConsoleForTask, Output) = console(&path, &format!("{}6\nn\n6\ny\n", add_input("a")));
+        console.process_input();
+        console.process_input();
+        assert_eq!(console.my_tasks.tasks.len(), 1);
+        console.process_input();
+        assert!(console.my_tasks.tasks.is_empty());
+    }
+
+    #[test]
+    fn duplicate_adds_a_fresh_copy_under_a_new_name()
+    {
+        let mut original: Task = task("report", Priority::High);
+        original.completed = true;
+        let mut manager: TaskManager = manager(vec![original]);
+        manager.clock = Box::new(FixedClock(at(5, 12)));
+        manager.duplicate("report").unwrap();
+        manager.duplicate("report").unwrap();
+
+        assert_eq!(names(&manager.tasks), ["report", "report (copy)", "report (copy 2)"]);
+        let copy: &Task = &manager.tasks[1];
+        assert_eq!(copy.add_time, at(5, 12));
+        assert_ne!(copy.add_time, manager.tasks[0].add_time);
+        assert_eq!(copy.priority, Priority::High);
+        assert!(!copy.completed);
+        assert_eq!(manager.duplicate("memo"), Err(TaskError::NotFound("memo".to_string())));
+    }
+
+    #[test]
+    fn duplicate_starts_the_copy_from_scratch()
+    {
+        let mut original: Task = task("report", Priority::High);
+        original.progress = 60;
+        original.time_spent = Duration::minutes(85);
+        original.subtasks = vec![SubTask { title: "outline".to_string(), done: true }];
+        let mut manager: TaskManager = manager(vec![task("blocker", Priority::Low), original]);
+        manager.set_blocker("report", Some(1)).unwrap();
+        manager.duplicate("report").unwrap();
+
+        let copy: &Task = &manager.tasks[2];
+        assert_eq!(copy.progress, 0);
+        assert_eq!(copy.time_spent, Duration::zero());
+        assert!(!copy.subtasks[0].done);
+        assert_eq!(copy.subtasks[0].title, "outline");
+        assert_eq!(copy.blocked_by, None);
+        assert!(!copy.to_display_string(false, at(1, 12), false).contains("spent"));
+        assert_eq!(manager.tasks[1].progress, 60);
+    }
+
+    #[test]
+    fn priority_orders_btree_map_keys()
+    {
+        let mut counts: BTreeMap<Priority, usize> = BTreeMap::new();
+        for priority in [Priority::High, Priority::None, Priority::VeryHigh, Priority::High, Priority::Low]
+        {
+            *counts.entry(priority).or_insert(0) += 1;
+        }
+        let keys: Vec<Priority> = counts.keys().copied().collect();
+        assert_eq!(keys, [Priority::None, Priority::Low, Priority::High, Priority::VeryHigh]);
+        assert_eq!(counts[&Priority::High], 2);
+        assert!(Priority::VeryHigh > Priority::High && Priority::Low > Priority::None);
+    }
+
+    #[test]
+    fn pretty_json_is_indented_and_compact_json_is_not()
+    {
+        let pretty_path: String = temp_path("pretty.json");
+        let compact_path: String = temp_path("compact.json");
+        let manager: TaskManager = manager(vec![task("a", Priority::Low)]);
+        manager.store_to_file(&pretty_path, true).unwrap();
+        manager.store_to_file(&compact_path, false).unwrap();
+
+        let pretty: String = std::fs::read_to_string(&pretty_path).unwrap();
+        let compact: String = std::fs::read_to_string(&compact_path).unwrap();
+        remove_files(&pretty_path);
+        remove_files(&compact_path);
+        assert!(pretty.contains('\n'));
+        assert!(!compact.contains('\n'));
+    }
+
+    #[test]
+    fn overdue_lists_past_due_tasks_most_overdue_first()
+    {
+        let due: [(&str, DateTime<Local>); 4] = [
+            ("yesterday", at(9, 12)),
+            ("next week", at(17, 12)),
+            ("last week", at(3, 12)),
+            ("an hour ago", at(10, 11))
+        ];
+        let mut manager: TaskManager = manager(due
+            .iter()
+            .map(|(name, due_date)| {
+                let mut task: Task = task(name, Priority::Low);
+                task.due_date = Some(*due_date);
+                task
+            })
+            .collect());
+        manager.clock = Box::new(FixedClock(at(10, 12)));
+
+        assert_eq!(names(manager.overdue()), ["last week", "yesterday", "an hour ago"]);
+    }
+
+    #[test]
+    fn archive_moves_completed_tasks_into_the_archive_file()
+    {
+        let path: String = temp_path("archive.json");
+        let mut old: TaskManager = manager(vec![task("archived before", Priority::Low)]);
+        old.tasks[0].completed = true;
+        old.store_to_file(&path, false).unwrap();
+
+        let mut manager: TaskManager = manager(vec![task("done", Priority::Low), task("open", Priority::Low)]);
+        manager.tasks[0].completed = true;
+        assert_eq!(manager.archive_completed(&path).unwrap(), 1);
+        assert_eq!(names(&manager.tasks), ["open"]);
+
+        let mut archive: TaskManager = TaskManager::new();
+        archive.read_from_file(&path).unwrap();
+        remove_files(&path);
+        assert_eq!(names(&archive.tasks), ["archived before", "done"]);
+    }
+
+    #[test]
+    fn add_rejects_an_empty_name()
+    {
+        let mut manager: TaskManager = TaskManager::new();
+        assert_eq!(manager.add(task("", Priority::Low)), Err(TaskError::Empty));
+        assert_eq!(manager.add(task(" \t", Priority::Low)), Err(TaskError::Empty));
+        assert!(manager.tasks.is_empty());
+    }
+
+    #[test]
+    fn add_command_asks_again_for_an_empty_name()
+    {
+        let (mut console, output): (ConsoleForTask, Output) = console(&temp_path("empty-name.json"), &add_input("   \nnamed"));
+        console.process_input();
+        assert!(output.text().contains("Task name cannot be empty"));
+        assert_eq!(names(&console.my_tasks.tasks), ["named"]);
+    }
+
+    #[test]
+    fn rename_changes_only_the_name()
+    {
+        let mut manager: TaskManager = manager(vec![task("reprot", Priority::High)]);
+        manager.rename("reprot", "report").unwrap();
+        assert_eq!(names(&manager.tasks), ["report"]);
+        assert_eq!(manager.tasks[0].priority, Priority::High);
+        // A change of letter case is no collision with itself
+        manager.rename("report", "Report").unwrap();
+        assert_eq!(names(&manager.tasks), ["Report"]);
+    }
+
+    #[test]
+    fn rename_fails_for_a_missing_task_or_a_taken_name()
+    {
+        let mut manager: TaskManager = manager(vec![task("a", Priority::Low), task("b", Priority::Low)]);
+        assert_eq!(manager.rename("c", "d"), Err(TaskError::NotFound("c".to_string())));
+        assert_eq!(manager.rename("a", "B"), Err(TaskError::Duplicate("B".to_string())));
+        assert_eq!(manager.rename("a", " "), Err(TaskError::Empty));
+        assert_eq!(names(&manager.tasks), ["a", "b"]);
+    }
+
+    #[test]
+    fn grouped_by_priority_skips_empty_buckets()
+    {
+        let manager: TaskManager = manager(vec![
+            task("low 1", Priority::Low),
+            task("very high", Priority::VeryHigh),
+            task("low 2", Priority::Low),
+            task("none", Priority::None)
+        ]);
+        let groups: Vec<(Priority, Vec<&str>)> = manager.grouped_by_priority()
+            .into_iter()
+            .map(|(priority, tasks)| (priority, names(tasks)))
+            .collect();
+        assert_eq!(groups, [
+            (Priority::VeryHigh, vec!["very high"]),
+            (Priority::Low, vec!["low 1", "low 2"]),
+            (Priority::None, vec!["none"])
+        ]);
+    }
+
+    #[test]
+    fn saving_leaves_no_temporary_file()
+    {
+        let path: String = temp_path("atomic.json");
+        manager(vec![task("a", Priority::Low)]).store_to_file(&path, true).unwrap();
+        let tmp_exists: bool = Path::new(&format!("{}.tmp", path)).exists();
+        let saved: bool = Path::new(&path).exists();
+        remove_files(&path);
+        assert!(saved);
+        assert!(!tmp_exists);
+    }
+
+    #[test]
+    fn failed_write_leaves_no_temporary_file()
+    {
+        let dir: String = temp_path("missing-dir");
+        let path: String = format!("{}/tasks.json", dir);
+        assert!(matches!(write_atomically(&path, b"[]"), Err(TaskError::Io(_))));
+        assert!(!Path::new(&format!("{}.tmp", path)).exists());
+    }
+
+    #[test]
+    fn changes_advance_modified_time_but_not_add_time()
+    {
+        let mut manager: TaskManager = manager(vec![task("draft", Priority::Low)]);
+        assert_eq!(manager.tasks[0].modified_time, at(1, 12));
+
+        manager.clock = Box::new(FixedClock(at(2, 12)));
+        manager.edit("draft", None, Some("longer".to_string()), None).unwrap();
+        assert_eq!(manager.tasks[0].modified_time, at(2, 12));
+        manager.clock = Box::new(FixedClock(at(3, 12)));
+        manager.rename("draft", "final").unwrap();
+        assert_eq!(manager.tasks[0].modified_time, at(3, 12));
+        manager.clock = Box::new(FixedClock(at(4, 12)));
+        manager.toggle_complete("final").unwrap();
+        assert_eq!(manager.tasks[0].modified_time, at(4, 12));
+        assert_eq!(manager.tasks[0].add_time, at(1, 12));
+    }
+
+    #[test]
+    fn modified_time_is_shown_only_after_a_change()
+    {
+        let mut manager: TaskManager = manager(vec![task("a", Priority::Low)]);
+        assert!(!manager.tasks[0].to_display_string(false, at(1, 12), false).contains("modified"));
+        manager.clock = Box::new(FixedClock(at(2, 8)));
+        manager.set_priority("a", Priority::High).unwrap();
+        assert!(manager.tasks[0].to_display_string(false, at(2, 8), false).contains("modified 02-06-2026  08:00:00"));
+    }
+
+    #[test]
+    fn tasks_without_a_modified_time_count_as_unchanged_since_added()
+    {
+        let path: String = temp_path("no-modified-time.json");
+        let mut file: serde_json::Value = serde_json::to_value(manager(vec![task("old", Priority::Low)]).to_file()).unwrap();
+        file["tasks"][0].as_object_mut().unwrap().remove("modified_time");
+        std::fs::write(&path, file.to_string()).unwrap();
+        let mut manager: TaskManager = TaskManager::new();
+        manager.read_from_file(&path).unwrap();
+        remove_files(&path);
+
+        assert_eq!(manager.tasks[0].modified_time, at(1, 12));
+        assert!(!manager.tasks[0].to_display_string(false, at(2, 8), false).contains("modified"));
+    }
+
+    #[test]
+    fn find_all_returns_every_matching_index()
+    {
+        let manager: TaskManager = manager(vec![
+            task("Call", Priority::Low),
+            task("Write", Priority::Low),
+            task("call", Priority::High),
+            task("Call", Priority::None)
+        ]);
+        assert_eq!(manager.find_all("call"), [0, 2, 3]);
+        assert_eq!(manager.find("call"), Some(0));
+        assert!(manager.find_all("Cal").is_empty());
+    }
+
+    #[test]
+    fn config_reads_the_given_fields_and_defaults_the_rest()
+    {
+        let path: String = temp_path("config.json");
+        std::fs::write(&path, r#"{"default_path": "work.yaml", "default_sort": "Name", "use_color": false}"#).unwrap();
+        let config: AppConfig = AppConfig::from_file(Path::new(&path));
+        remove_files(&path);
+
+        assert_eq!(config.default_path, "work.yaml");
+        assert!(matches!(config.default_sort, SortKey::Name));
+        assert!(!config.use_color);
+        assert!(config.pretty_json);
+        assert_eq!(config.max_tasks, None);
+    }
+
+    #[test]
+    fn config_falls_back_to_defaults_without_a_usable_file()
+    {
+        let path: String = temp_path("no-config.json");
+        let missing: AppConfig = AppConfig::from_file(Path::new(&path));
+        assert_eq!(missing.default_path, DEFAULT_PATH);
+        assert!(matches!(missing.default_sort, SortKey::Priority));
+        assert!(missing.use_color && missing.pretty_json);
+
+        std::fs::write(&path, "{ not json").unwrap();
+        let malformed: AppConfig = AppConfig::from_file(Path::new(&path));
+        remove_files(&path);
+        assert_eq!(malformed.default_path, DEFAULT_PATH);
+    }
+
+    #[test]
+    fn run_cli_applies_the_task_limit_of_the_config()
+    {
+        let path: String = temp_path("cli-limit.json");
+        let config: AppConfig = AppConfig { max_tasks: Some(1), ..cli_config(&path) };
+        assert_eq!(run_cli(args(&["add", "a"]), &config, &mut io::sink(), false), 0);
+        assert_eq!(run_cli(args(&["add", "b"]), &config, &mut io::sink(), false), 1);
+        remove_files(&path);
+    }
+
+    #[test]
+    fn set_priority_changes_the_priority()
+    {
+        let mut manager: TaskManager = manager(vec![task("a", Priority::Low)]);
+        manager.set_priority("A", Priority::VeryHigh).unwrap();
+        assert_eq!(manager.tasks[0].priority, Priority::VeryHigh);
+        assert_eq!(manager.set_priority("b", Priority::High), Err(TaskError::NotFound("b".to_string())));
+    }
+
+    #[test]
+    fn import_lines_adds_a_task_per_non_empty_line()
+    {
+        let path: String = temp_path("lines.txt");
+        std::fs::write(&path, "Buy milk\n   \n  Call Bob  \n").unwrap();
+        let mut manager: TaskManager = TaskManager::new();
+        let count: usize = manager.import_lines(&path).unwrap();
+        remove_files(&path);
+
+        assert_eq!(count, 2);
+        assert_eq!(names(&manager.tasks), ["Buy milk", "Call Bob"]);
+        assert!(manager.tasks.iter().all(|task: &Task| task.priority == Priority::Medium && task.description.is_empty()));
+    }
+
+    #[test]
+    fn due_today_compares_calendar_days()
+    {
+        let due: [(&str, DateTime<Local>); 4] = [
+            ("yesterday", at(9, 20)),
+            ("this morning", at(10, 9)),
+            ("tonight", at(10, 23)),
+            ("tomorrow", at(11, 1))
+        ];
+        let mut manager: TaskManager = manager(due
+            .iter()
+            .map(|(name, due_date)| {
+                let mut task: Task = task(name, Priority::Low);
+                task.due_date = Some(*due_date);
+                task
+            })
+            .collect());
+        manager.clock = Box::new(FixedClock(at(10, 17)));
+
+        assert_eq!(names(manager.due_today()), ["this morning", "tonight"]);
+        manager.tasks[2].completed = true;
+        assert_eq!(names(manager.due_today()), ["this morning"]);
+    }
+
+    #[test]
+    fn legacy_bare_array_files_are_migrated()
+    {
+        let path: String = temp_path("legacy.json");
+        let legacy: String = serde_json::to_string(&vec![task("old a", Priority::Low), task("old b", Priority::High)]).unwrap();
+        std::fs::write(&path, legacy.replace("\"id\":0,", "")).unwrap();
+        let mut manager: TaskManager = TaskManager::new();
+        manager.read_from_file(&path).unwrap();
+        remove_files(&path);
+
+        assert_eq!(names(&manager.tasks), ["old a", "old b"]);
+        assert_eq!(manager.tasks.iter().map(|task: &Task| task.id).collect::<Vec<u64>>(), [1, 2]);
+        assert_eq!(manager.next_id, 3);
+    }
+
+    #[test]
+    fn versioned_files_load_and_newer_versions_are_refused()
+    {
+        let path: String = temp_path("versioned.json");
+        let mut stored: Task = task("new", Priority::Low);
+        stored.id = 3;
+        let tasks: String = serde_json::to_string(&vec![stored]).unwrap();
+        std::fs::write(&path, format!(r#"{{"version": 2, "next_id": 7, "tasks": {}}}"#, tasks)).unwrap();
+        let mut manager: TaskManager = TaskManager::new();
+        manager.read_from_file(&path).unwrap();
+        assert_eq!(names(&manager.tasks), ["new"]);
+        assert_eq!(manager.next_id, 7);
+
+        std::fs::write(&path, format!(r#"{{"version": {}, "tasks": []}}"#, FILE_VERSION + 1)).unwrap();
+        let result: Result<(), TaskError> = manager.read_from_file(&path);
+        remove_files(&path);
+        assert!(matches!(result, Err(TaskError::Parse(_))));
+        assert_eq!(names(&manager.tasks), ["new"]);
+    }
+
+    #[test]
+    fn word_counts_ignore_extra_whitespace()
+    {
+        let mut spaced: Task = task("spaced", Priority::Low);
+        spaced.description = "  many   spaces\n\nand  lines ".to_string();
+        let manager: TaskManager = manager(vec![spaced, task("empty", Priority::Low)]);
+
+        assert_eq!(manager.tasks[0].description_word_count(), 4);
+        assert_eq!(manager.tasks[1].description_word_count(), 0);
+        assert_eq!(manager.total_words(), 4);
+    }
+
+    #[test]
+    fn due_within_returns_tasks_due_inside_the_window()
+    {
+        let now: DateTime<Local> = at(10, 12);
+        let mut manager: TaskManager = manager([1, 10, 100, -1]
+            .iter()
+            .map(|hours: &i64| {
+                let mut task: Task = task(&format!("{}h", hours), Priority::Low);
+                task.due_date = Some(now + Duration::hours(*hours));
+                task
+            })
+            .collect());
+        manager.clock = Box::new(FixedClock(now));
+
+        assert_eq!(names(manager.due_within(24).unwrap()), ["1h", "10h"]);
+    }
+
+    #[test]
+    fn due_within_rejects_a_window_past_the_last_date()
+    {
+        let manager: TaskManager = manager(vec![task("a", Priority::Low)]);
+        assert!(matches!(manager.due_within(1_000_000_000_000), Err(TaskError::Invalid(_))));
+        assert!(manager.due_within(i64::MAX).is_err());
+    }
+
+    #[test]
+    fn swap_exchanges_two_tasks()
+    {
+        let mut manager: TaskManager = manager(vec![task("a", Priority::Low), task("b", Priority::Low), task("c", Priority::Low)]);
+        manager.swap("a", "C").unwrap();
+        assert_eq!(names(&manager.tasks), ["c", "b", "a"]);
+    }
+
+    #[test]
+    fn swap_fails_if_either_task_is_missing()
+    {
+        let mut manager: TaskManager = manager(vec![task("a", Priority::Low), task("b", Priority::Low)]);
+        assert_eq!(manager.swap("x", "b"), Err(TaskError::NotFound("x".to_string())));
+        assert_eq!(manager.swap("a", "y"), Err(TaskError::NotFound("y".to_string())));
+        assert_eq!(manager.swap("x", "y"), Err(TaskError::NotFound("x".to_string())));
+        assert_eq!(names(&manager.tasks), ["a", "b"]);
+    }
+
+    #[test]
+    fn priority_picker_accepts_zero_for_none()
+    {
+        let (mut console, _): (ConsoleForTask, Output) = console(&temp_path("picker.json"), "0\n");
+        assert_eq!(console.prompt_priority(Priority::Medium).unwrap(), Priority::None);
+    }
+
+    #[test]
+    fn priority_picker_asks_again_after_invalid_input()
+    {
+        let (mut console, output): (ConsoleForTask, Output) = console(&temp_path("picker-retry.json"), "9\nurgent\n0\n");
+        assert_eq!(console.prompt_priority(Priority::Low).unwrap(), Priority::None);
+        assert_eq!(output.text().matches("Enter index of priority").count(), 3);
+    }
+
+    #[test]
+    fn session_ends_when_input_runs_out()
+    {
+        let path: String = temp_path("eof.json");
+        let (mut console, output): (ConsoleForTask, Output) = console(&path, "5\n");
+        assert!(console.process_input());
+        assert!(!console.process_input());
+        remove_files(&path);
+        assert!(!output.text().contains("Invalid input"));
+    }
+
+    #[test]
+    fn input_reports_the_end_of_input()
+    {
+        let (mut console, _): (ConsoleForTask, Output) = console(&temp_path("eof-input.json"), "");
+        assert_eq!(console.input("> ").unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn add_then_list_through_in_memory_io()
+    {
+        let path: String = temp_path("add-list.json");
+        let input: String = "1\nWrite report\nnumbers for\nthe board\n.\n\n3\nwork\n\n\n40\n5\n".to_string();
+        let (mut console, output): (ConsoleForTask, Output) = console(&path, &input);
+        run(&mut console);
+        remove_files(&path);
+
+        let text: String = output.text();
+        let listing: &str = &text[text.find("Tasks (1):").expect("tasks are listed")..];
+        assert!(listing.contains("#1 [ ] Write report [40%] | High |"));
+        assert!(listing.contains("tags: work"));
+        assert!(listing.contains("\"numbers for\n the board\""));
+    }
+
+    #[test]
+    fn peek_shows_the_last_task_without_removing_it()
+    {
+        let manager: TaskManager = manager(vec![task("a", Priority::Low), task("b", Priority::Low)]);
+        assert_eq!(manager.peek().map(|task: &Task| task.name.as_str()), Some("b"));
+        assert_eq!(names(&manager.tasks), ["a", "b"]);
+        assert!(TaskManager::new().peek().is_none());
+    }
+
+    #[test]
+    fn backup_holds_the_previous_save()
+    {
+        let path: String = temp_path("backup.json");
+        let mut manager: TaskManager = manager(vec![task("first", Priority::Low)]);
+        manager.store_to_file(&path, true).unwrap();
+        let first: String = std::fs::read_to_string(&path).unwrap();
+        assert!(!Path::new(&backup_path(&path)).exists());
+        manager.push(task("second", Priority::Low)).unwrap();
+        manager.store_to_file(&path, true).unwrap();
+
+        let backup: String = std::fs::read_to_string(backup_path(&path)).unwrap();
+        remove_files(&path);
+        assert_eq!(backup, first);
+    }
+
+    #[test]
+    fn yaml_saves_keep_a_backup_too()
+    {
+        let path: String = temp_path("backup.yaml");
+        let mut manager: TaskManager = manager(vec![task("first", Priority::Low)]);
+        manager.store_to_yaml(&path).unwrap();
+        let first: String = std::fs::read_to_string(&path).unwrap();
+        manager.push(task("second", Priority::Low)).unwrap();
+        manager.store_to_yaml(&path).unwrap();
+
+        let backup: String = std::fs::read_to_string(backup_path(&path)).unwrap();
+        remove_files(&path);
+        assert_eq!(backup, first);
+    }
+
+    #[test]
+    fn suggest_next_prefers_priority_over_due_date()
+    {
+        let mut soon: Task = task("soon", Priority::Low);
+        soon.due_date = Some(at(2, 9));
+        let mut done: Task = task("done", Priority::VeryHigh);
+        done.completed = true;
+        let manager: TaskManager = manager(vec![soon, task("important", Priority::High), done]);
+        assert_eq!(manager.suggest_next().map(|task: &Task| task.name.as_str()), Some("important"));
+    }
+
+    #[test]
+    fn suggest_next_breaks_ties_by_the_nearest_due_date()
+    {
+        let mut later: Task = task("later", Priority::High);
+        later.due_date = Some(at(20, 9));
+        let mut sooner: Task = task("sooner", Priority::High);
+        sooner.due_date = Some(at(5, 9));
+        let manager: TaskManager = manager(vec![task("undated", Priority::High), later, sooner]);
+        assert_eq!(manager.suggest_next().map(|task: &Task| task.name.as_str()), Some("sooner"));
+        assert!(TaskManager::new().suggest_next().is_none());
+    }
+
+    #[test]
+    fn with_time_keeps_the_given_add_time()
+    {
+        let backdated: DateTime<Local> = Local.with_ymd_and_hms(2019, 3, 4, 5, 6, 7).unwrap();
+        let task: Task = Task::with_time("old".to_string(), "desc".to_string(), Priority::Low, backdated);
+        assert_eq!(task.add_time, backdated);
+        assert_eq!(task.modified_time, backdated);
+    }
+
+    #[test]
+    fn parse_file_flag_splits_off_the_path()
+    {
+        assert_eq!(
+            parse_file_flag(args(&["--file", "work.json", "list", "--all"])),
+            Ok((Some("work.json".to_string()), args(&["list", "--all"])))
+        );
+        assert_eq!(parse_file_flag(args(&["--file", "work.json"])), Ok((Some("work.json".to_string()), Vec::new())));
+        assert_eq!(parse_file_flag(args(&["list"])), Ok((None, args(&["list"]))));
+        assert!(matches!(parse_file_flag(args(&["--file"])), Err(TaskError::Invalid(_))));
+        assert!(parse_file_flag(args(&["--file", " "])).is_err());
+    }
+
+    #[test]
+    fn unknown_flags_fail_with_usage()
+    {
+        let path: String = temp_path("unknown-flag.json");
+        let (_, rest): (Option<String>, Vec<String>) = parse_file_flag(args(&["--frobnicate"])).unwrap();
+        assert_eq!(run_cli(rest, &cli_config(&path), &mut io::sink(), false), 1);
+    }
+
+    #[test]
+    fn dedupe_keeps_the_first_of_each_name()
+    {
+        let mut first: Task = task("Report", Priority::High);
+        first.description = "keep me".to_string();
+        let mut manager: TaskManager = manager(vec![first, task("Call", Priority::Low), task("report", Priority::Low)]);
+
+        assert_eq!(manager.dedupe(), 1);
+        assert_eq!(names(&manager.tasks), ["Report", "Call"]);
+        assert_eq!(manager.tasks[0].description, "keep me");
+        assert_eq!(manager.dedupe(), 0);
+    }
+
+    #[test]
+    fn completed_tasks_render_plainly_without_color()
+    {
+        let mut done: Task = task("done", Priority::High);
+        done.completed = true;
+        let manager: TaskManager = manager(vec![done]);
+
+        let plain: String = manager.tasks[0].to_display_string(false, at(1, 12), false);
+        assert!(plain.starts_with("#1 [x] done "));
+        assert!(!plain.contains('\x1b'));
+        assert!(manager.tasks[0].to_display_string(true, at(1, 12), false).contains("[x] \x1b[2;9mdone\x1b[0m"));
+    }
+
+    #[test]
+    fn jsonl_export_and_import_round_trip()
+    {
+        let path: String = temp_path("round-trip.jsonl");
+        let mut report: Task = task("report", Priority::High);
+        report.description = "two\nlines".to_string();
+        let original: TaskManager = manager(vec![report, task("call", Priority::Low)]);
+        original.export_jsonl(&path).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 2);
+
+        let mut imported: TaskManager = TaskManager::new();
+        assert_eq!(imported.import_jsonl(&path).unwrap(), (2, 0));
+        remove_files(&path);
+        assert_eq!(names(&imported.tasks), ["report", "call"]);
+        assert_eq!(imported.tasks[0].description, "two\nlines");
+        assert_eq!(imported.tasks[1].priority, Priority::Low);
+    }
+
+    #[test]
+    fn import_jsonl_skips_malformed_lines()
+    {
+        let path: String = temp_path("malformed.jsonl");
+        let line = |name: &str| serde_json::to_string(&task(name, Priority::Low)).unwrap();
+        std::fs::write(&path, format!("{}\n{{\"name\": broken\n\n{}\n", line("first"), line("last"))).unwrap();
+        let mut manager: TaskManager = TaskManager::new();
+        let result: Result<(usize, usize), TaskError> = manager.import_jsonl(&path);
+        remove_files(&path);
+
+        assert_eq!(result, Ok((2, 1)));
+        assert_eq!(names(&manager.tasks), ["first", "last"]);
+    }
+
+    #[test]
+    fn added_between_is_inclusive_and_accepts_either_order()
+    {
+        let mut manager: TaskManager = manager((1..=5)
+            .map(|day: u32| Task::with_time(format!("day {}", day), String::new(), Priority::Low, at(day, 12)))
+            .collect());
+        manager.push(Task::with_time("late on day 4".to_string(), String::new(), Priority::Low, at(4, 23))).unwrap();
+
+        assert_eq!(names(manager.added_between(at(2, 12), at(4, 12))), ["day 2", "day 3", "day 4"]);
+        assert_eq!(names(manager.added_between(at(4, 12), at(2, 12))), ["day 2", "day 3", "day 4"]);
+        assert!(manager.added_between(at(6, 0), at(7, 0)).is_empty());
+    }
+
+    #[test]
+    fn push_past_the_cap_fails()
+    {
+        let mut manager: TaskManager = TaskManager::new();
+        manager.max_tasks = Some(2);
+        manager.push(task("a", Priority::Low)).unwrap();
+        manager.push(task("b", Priority::Low)).unwrap();
+        assert_eq!(manager.push(task("c", Priority::Low)), Err(TaskError::Full(2)));
+        assert_eq!(manager.tasks.len(), 2);
+    }
+
+    #[test]
+    fn imports_past_the_cap_add_nothing()
+    {
+        let path: String = temp_path("cap.txt");
+        std::fs::write(&path, "one\ntwo\n").unwrap();
+        let mut manager: TaskManager = manager(vec![task("a", Priority::Low)]);
+        manager.max_tasks = Some(2);
+        let result: Result<usize, TaskError> = manager.import_lines(&path);
+        remove_files(&path);
+        assert_eq!(result, Err(TaskError::Full(2)));
+        assert_eq!(names(&manager.tasks), ["a"]);
+    }
+
+    #[test]
+    fn jsonl_imports_past_the_cap_add_nothing()
+    {
+        let path: String = temp_path("cap.jsonl");
+        let line = |name: &str| serde_json::to_string(&task(name, Priority::Low)).unwrap();
+        std::fs::write(&path, format!("{}\nbroken\n{}\n", line("one"), line("two"))).unwrap();
+        let mut manager: TaskManager = manager(vec![task("a", Priority::Low)]);
+        manager.max_tasks = Some(2);
+        let result: Result<(usize, usize), TaskError> = manager.import_jsonl(&path);
+        remove_files(&path);
+        assert_eq!(result, Err(TaskError::Full(2)));
+        assert_eq!(names(&manager.tasks), ["a"]);
+    }
+
+    #[test]
+    fn complete_is_idempotent_and_uncomplete_reverses_it()
+    {
+        let mut manager: TaskManager = manager(vec![task("a", Priority::Low)]);
+        manager.complete("a").unwrap();
+        assert!(manager.tasks[0].completed);
+        manager.complete("a").unwrap();
+        assert!(manager.tasks[0].completed);
+
+        manager.uncomplete("a").unwrap();
+        assert!(!manager.tasks[0].completed);
+        manager.uncomplete("a").unwrap();
+        assert!(!manager.tasks[0].completed);
+        assert_eq!(manager.complete("ghost"), Err(TaskError::NotFound("ghost".to_string())));
+    }
+
+    #[test]
+    fn color_labels_are_stored_and_optional()
+    {
+        let mut labelled: Task = task("a", Priority::Low);
+        labelled.color = Some(TaskColor::Blue);
+        let json: String = serde_json::to_string(&labelled).unwrap();
+        assert!(json.contains("\"color\":\"Blue\""));
+        assert_eq!(serde_json::from_str::<Task>(&json).unwrap().color, Some(TaskColor::Blue));
+
+        let mut value: serde_json::Value = serde_json::to_value(task("b", Priority::Low)).unwrap();
+        value.as_object_mut().unwrap().remove("color");
+        assert_eq!(serde_json::from_value::<Task>(value).unwrap().color, None);
+    }
+
+    #[test]
+    fn color_labels_are_named_without_color()
+    {
+        let mut labelled: Task = task("a", Priority::Low);
+        labelled.color = Some(TaskColor::Green);
+        assert!(labelled.to_display_string(false, at(1, 12), false).starts_with("(green) #0 [ ] a"));
+        assert!(labelled.to_display_string(true, at(1, 12), false).starts_with("\x1b[32m●\x1b[0m #0"));
+        assert!(task("b", Priority::Low).to_display_string(false, at(1, 12), false).starts_with("#0 [ ] b"));
+    }
+
+    #[test]
+    fn session_history_records_changes_in_order()
+    {
+        let path: String = temp_path("history.json");
+        let (mut console, output): (ConsoleForTask, Output) = console(&path, &format!("{}2\n50\n", add_input("a")));
+        console.my_tasks.clock = Box::new(FixedClock(at(3, 9)));
+        run(&mut console);
+        remove_files(&path);
+
+        let actions: Vec<&str> = console.log.iter().map(|entry: &LogEntry| entry.action.as_str()).collect();
+        assert_eq!(actions, ["Added task \"a\"", "Removed task \"a\""]);
+        assert!(console.log.iter().all(|entry: &LogEntry| entry.time == at(3, 9)));
+        assert!(output.text().contains("03-06-2026  09:00:00  Removed task \"a\""));
+    }
+
+    #[test]
+    fn bulk_removals_and_small_edits_are_recorded()
+    {
+        let path: String = temp_path("history-edits.json");
+        let input: String = format!("{}{}36\na\n4\n34\na\nc\n31\nc\n11\nb\n23\n44\n6\ny\n", add_input("a"), add_input("b"));
+        let (mut console, _): (ConsoleForTask, Output) = console(&path, &input);
+        run(&mut console);
+        remove_files(&path);
+
+        let actions: Vec<&str> = console.log.iter().map(|entry: &LogEntry| entry.action.as_str()).collect();
+        assert_eq!(actions, [
+            "Added task \"a\"",
+            "Added task \"b\"",
+            "Changed priority of task \"a\" to Very High",
+            "Renamed task \"a\" to \"c\"",
+            "Duplicated task \"c\"",
+            "Removed 1 completed tasks",
+            "Removed all tasks"
+        ]);
+    }
+
+    #[test]
+    fn a_typo_suggests_the_intended_task_first()
+    {
+        let manager: TaskManager = manager(vec![task("Laundry", Priority::Low), task("Groceries", Priority::Low), task("Gym", Priority::Low)]);
+        let matches: Vec<(usize, usize)> = manager.find_fuzzy("Groceris");
+        assert_eq!(matches[0], (1, 1));
+        assert_eq!(matches.len(), 3);
+        assert!(matches.windows(2).all(|pair: &[(usize, usize)]| pair[0].1 <= pair[1].1));
+    }
+
+    #[test]
+    fn removing_a_misspelled_task_offers_close_names()
+    {
+        let path: String = temp_path("fuzzy.json");
+        let (mut console, output): (ConsoleForTask, Output) = console(&path, &format!("{}3\nGroceris\n", add_input("Groceries")));
+        run(&mut console);
+        remove_files(&path);
+        assert!(output.text().contains("Did you mean \"Groceries\"?"));
+        assert_eq!(console.my_tasks.tasks.len(), 1);
+    }
+
+    #[test]
+    fn markdown_export_writes_a_checklist()
+    {
+        let path: String = temp_path("checklist.md");
+        let mut done: Task = task("Ship", Priority::High);
+        done.completed = true;
+        done.description = "first line\n\nsecond line".to_string();
+        let manager: TaskManager = manager(vec![done, task("Plan", Priority::None)]);
+        manager.export_markdown(&path).unwrap();
+
+        let markdown: String = std::fs::read_to_string(&path).unwrap();
+        remove_files(&path);
+        assert_eq!(markdown, "- [x] Ship **High**\n  first line\n  second line\n- [ ] Plan\n");
+    }
+
+    #[test]
+    fn snooze_moves_an_existing_due_date()
+    {
+        let mut dated: Task = task("dated", Priority::Low);
+        dated.due_date = Some(at(2, 9));
+        let mut manager: TaskManager = manager(vec![dated]);
+        manager.clock = Box::new(FixedClock(at(10, 12)));
+
+        manager.snooze("dated", 3).unwrap();
+        assert_eq!(manager.tasks[0].due_date, Some(at(5, 9)));
+        assert_eq!(manager.tasks[0].modified_time, at(10, 12));
+    }
+
+    #[test]
+    fn snooze_counts_from_now_without_a_due_date()
+    {
+        let mut manager: TaskManager = manager(vec![task("undated", Priority::Low)]);
+        manager.clock = Box::new(FixedClock(at(10, 12)));
+
+        manager.snooze("undated", 2).unwrap();
+        assert_eq!(manager.tasks[0].due_date, Some(at(12, 12)));
+        assert_eq!(manager.snooze("ghost", 1), Err(TaskError::NotFound("ghost".to_string())));
+        assert!(matches!(manager.snooze("undated", i64::MAX), Err(TaskError::Invalid(_))));
+    }
+
+    #[test]
+    fn read_from_file_with_replaces_or_appends()
+    {
+        let path: String = temp_path("replace-or-merge.json");
+        manager(vec![task("stored", Priority::Low)]).store_to_file(&path, true).unwrap();
+
+        let mut replaced: TaskManager = manager(vec![task("current", Priority::Low)]);
+        assert_eq!(replaced.read_from_file_with(&path, true), Ok(1));
+        let mut merged: TaskManager = manager(vec![task("current", Priority::Low)]);
+        assert_eq!(merged.read_from_file_with(&path, false), Ok(1));
+        let mut wrapped: TaskManager = manager(vec![task("current", Priority::Low)]);
+        wrapped.read_from_file(&path).unwrap();
+        remove_files(&path);
+
+        assert_eq!(names(&replaced.tasks), ["stored"]);
+        assert_eq!(names(&merged.tasks), ["current", "stored"]);
+        assert_eq!(names(&wrapped.tasks), ["stored"]);
+    }
+
+    #[test]
+    fn read_command_replaces_unless_asked_to_merge()
+    {
+        let path: String = temp_path("read-command.json");
+        manager(vec![task("stored", Priority::Low)]).store_to_file(&path, true).unwrap();
+        let (mut replacing, _): (ConsoleForTask, Output) = console(&path, &format!("{}8\n\n\n", add_input("current")));
+        run(&mut replacing);
+        let (mut merging, _): (ConsoleForTask, Output) = console(&path, &format!("{}8\n\ny\n", add_input("current")));
+        run(&mut merging);
+        remove_files(&path);
+
+        assert_eq!(names(&replacing.my_tasks.tasks), ["stored"]);
+        assert_eq!(names(&merging.my_tasks.tasks), ["stored", "current", "stored"]);
+    }
+
+    #[test]
+    fn validate_rejects_a_due_date_before_the_add_time()
+    {
+        let mut early: Task = Task::with_time("early".to_string(), String::new(), Priority::Low, at(5, 12));
+        early.due_date = Some(at(4, 12));
+        assert!(matches!(early.validate(), Err(TaskError::Invalid(_))));
+
+        early.due_date = Some(at(6, 12));
+        assert_eq!(early.validate(), Ok(()));
+        early.due_date = None;
+        assert_eq!(early.validate(), Ok(()));
+    }
+
+    #[test]
+    fn adding_with_a_past_due_date_asks_again()
+    {
+        let path: String = temp_path("past-due.json");
+        let input: &str = "1\nlate\n.\n01-01-2020 10:00\n+2d\n\n\n\n\n\n";
+        let (mut console, output): (ConsoleForTask, Output) = console(&path, input);
+        run(&mut console);
+        remove_files(&path);
+
+        assert!(output.text().contains("cannot be due before it was added"));
+        let task: &Task = &console.my_tasks.tasks[0];
+        assert!(task.due_date.unwrap() > task.add_time);
+    }
+
+    #[test]
+    fn prompt_priority_falls_back_to_the_default()
+    {
+        let path: String = temp_path("prompt-priority.json");
+        let (mut console, output): (ConsoleForTask, Output) = console(&path, "\n3\nurgent\n0\n");
+        assert_eq!(console.prompt_priority(Priority::Low).unwrap(), Priority::Low);
+        assert_eq!(console.prompt_priority(Priority::Low).unwrap(), Priority::High);
+        assert_eq!(console.prompt_priority(Priority::Low).unwrap(), Priority::None);
+        remove_files(&path);
+        assert!(output.text().contains("[Low]"));
+    }
+
+    #[test]
+    fn listing_hides_completed_tasks_unless_asked()
+    {
+        let mut done: Task = task("done", Priority::Low);
+        done.completed = true;
+        let manager: TaskManager = manager(vec![task("open", Priority::Low), done]);
+        assert_eq!(names(manager.incomplete()), ["open"]);
+
+        let default: String = manager.to_display_string(false, false);
+        assert!(default.starts_with("Tasks (1):"));
+        assert!(!default.contains("done"));
+        assert!(default.ends_with("1 completed hidden\n"));
+
+        let all: String = manager.to_display_string(false, true);
+        assert!(all.starts_with("Tasks (2):"));
+        assert!(all.contains("[x] done"));
+        assert!(!all.contains("hidden"));
+    }
+
+    #[test]
+    fn remove_at_takes_the_task_at_that_position()
+    {
+        let mut manager: TaskManager = manager(vec![task("a", Priority::Low), task("b", Priority::Low), task("c", Priority::Low)]);
+        assert_eq!(manager.remove_at(1).map(|task: Task| task.name), Ok("b".to_string()));
+        assert_eq!(names(&manager.tasks), ["a", "c"]);
+    }
+
+    #[test]
+    fn remove_at_fails_past_the_end()
+    {
+        let mut manager: TaskManager = manager(vec![task("a", Priority::Low)]);
+        assert_eq!(manager.remove_at(1).err(), Some(TaskError::Invalid("No task number 2".to_string())));
+        assert!(TaskManager::new().remove_at(0).is_err());
+        assert_eq!(names(&manager.tasks), ["a"]);
+    }
+
+    #[test]
+    fn age_is_formatted_in_days_and_hours()
+    {
+        let old: Task = task("old", Priority::Low);
+        assert_eq!(old.age(at(3, 16)), Duration::hours(52));
+        assert_eq!(format_duration(old.age(at(3, 16))), "2d 4h");
+        assert_eq!(format_duration(old.age(at(1, 17))), "5h");
+        assert_eq!(format_duration(old.age(at(1, 11))), "0h");
+    }
+
+    #[test]
+    fn oldest_skips_completed_tasks()
+    {
+        let mut done: Task = Task::with_time("done".to_string(), String::new(), Priority::Low, at(1, 0));
+        done.completed = true;
+        let manager: TaskManager = manager(vec![
+            Task::with_time("newer".to_string(), String::new(), Priority::Low, at(5, 0)),
+            done,
+            Task::with_time("older".to_string(), String::new(), Priority::Low, at(2, 0))
+        ]);
+        assert_eq!(manager.oldest().map(|task: &Task| task.name.as_str()), Some("older"));
+        assert!(TaskManager::new().oldest().is_none());
+    }
+
+    #[test]
+    fn file_var_sets_the_default_path()
+    {
+        let _lock: std::sync::MutexGuard<()> = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        std::env::set_var(FILE_VAR, "from-env.json");
+        let path: Option<String> = env_path();
+        let config: AppConfig = AppConfig::load();
+        std::env::remove_var(FILE_VAR);
+
+        assert_eq!(path.as_deref(), Some("from-env.json"));
+        assert_eq!(config.default_path, "from-env.json");
+    }
+
+    #[test]
+    fn default_path_is_kept_without_the_file_var()
+    {
+        let _lock: std::sync::MutexGuard<()> = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        std::env::remove_var(FILE_VAR);
+        let unset: Option<String> = env_path();
+        let config: AppConfig = AppConfig::load();
+        std::env::set_var(FILE_VAR, "  ");
+        let blank: Option<String> = env_path();
+        std::env::remove_var(FILE_VAR);
+
+        assert_eq!(unset, None);
+        assert_eq!(blank, None);
+        let configured: String = match std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"))
+        {
+            Some(home) => AppConfig::from_file(&Path::new(&home).join(CONFIG_FILE)).default_path,
+            None       => DEFAULT_PATH.to_string()
+        };
+        assert_eq!(config.default_path, configured);
+    }
+
+    #[test]
+    fn unknown_fields_are_ignored_on_load()
+    {
+        let mut value: serde_json::Value = serde_json::to_value(task("a", Priority::High)).unwrap();
+        value["foo"] = serde_json::json!("from a newer version");
+        let parsed: Task = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed.name, "a");
+        assert_eq!(parsed.priority, Priority::High);
+
+        let path: String = temp_path("unknown-fields.json");
+        let mut file: serde_json::Value = serde_json::to_value(manager(vec![task("b", Priority::Low)]).to_file()).unwrap();
+        file["foo"] = serde_json::json!({ "bar": 1 });
+        std::fs::write(&path, file.to_string()).unwrap();
+        let mut loaded: TaskManager = TaskManager::new();
+        let result: Result<(), TaskError> = loaded.read_from_file(&path);
+        remove_files(&path);
+        assert_eq!(result, Ok(()));
+        assert_eq!(names(&loaded.tasks), ["b"]);
+    }
+
+    #[test]
+    fn restore_backup_loads_the_previous_save()
+    {
+        let path: String = temp_path("restore.json");
+        let mut saved: TaskManager = manager(vec![task("first", Priority::Low)]);
+        saved.store_to_file(&path, true).unwrap();
+        saved.push(task("second", Priority::Low)).unwrap();
+        saved.store_to_file(&path, true).unwrap();
+
+        let mut restored: TaskManager = manager(vec![task("current", Priority::Low)]);
+        let result: Result<(), TaskError> = restored.restore_backup(&path);
+        remove_files(&path);
+        assert_eq!(result, Ok(()));
+        assert_eq!(names(&restored.tasks), ["first"]);
+    }
+
+    #[test]
+    fn restore_backup_fails_without_a_backup()
+    {
+        let path: String = temp_path("no-backup.json");
+        let mut manager: TaskManager = manager(vec![task("current", Priority::Low)]);
+        assert!(matches!(manager.restore_backup(&path), Err(TaskError::Io(_))));
+        assert_eq!(names(&manager.tasks), ["current"]);
+    }
+
+    #[test]
+    fn histogram_bars_scale_with_the_counts()
+    {
+        let mut tasks: Vec<Task> = (0..4).map(|i: i32| task(&format!("high {}", i), Priority::High)).collect();
+        tasks.push(task("low 1", Priority::Low));
+        tasks.push(task("low 2", Priority::Low));
+        tasks.push(task("very high", Priority::VeryHigh));
+        let histogram: String = manager(tasks).priority_histogram();
+
+        let bars: Vec<(&str, usize)> = histogram
+            .lines()
+            .map(|line: &str| (line, line.chars().filter(|c: &char| *c == '█').count()))
+            .collect();
+        assert_eq!(bars.len(), 5);
+        assert_eq!(bars[0], ("Very High ██████████ 1", HISTOGRAM_WIDTH / 4));
+        assert_eq!(bars[1].1, HISTOGRAM_WIDTH);
+        assert!(bars[1].0.ends_with(" 4"));
+        assert_eq!(bars[2], ("Medium     0", 0));
+        assert_eq!(bars[3].1, HISTOGRAM_WIDTH / 2);
+        assert_eq!(bars[4], ("None       0", 0));
+    }
+
+    #[test]
+    fn projects_keep_their_tasks_apart()
+    {
+        let mut manager: TaskManager = TaskManager::new();
+        manager.projects.create("work").unwrap();
+        manager.projects.create("home").unwrap();
+        assert!(manager.projects.create("work").is_err());
+
+        manager.switch_project("work").unwrap();
+        manager.push(task("report", Priority::High)).unwrap();
+        manager.switch_project("home").unwrap();
+        manager.push(task("laundry", Priority::Low)).unwrap();
+        assert_eq!(names(&manager.tasks), ["laundry"]);
+        assert!(manager.find("report").is_none());
+
+        manager.switch_project("work").unwrap();
+        assert_eq!(names(&manager.tasks), ["report"]);
+        assert_eq!(manager.projects.names(), ["default", "home", "work"]);
+        assert_eq!(manager.switch_project("hobby"), Err(TaskError::NoProject("hobby".to_string())));
+    }
+
+    #[test]
+    fn projects_are_saved_in_one_file()
+    {
+        let path: String = temp_path("projects.json");
+        let mut manager: TaskManager = manager(vec![task("inbox", Priority::Low)]);
+        manager.projects.create("work").unwrap();
+        manager.switch_project("work").unwrap();
+        manager.push(task("report", Priority::High)).unwrap();
+        manager.store_to_file(&path, true).unwrap();
+
+        let mut loaded: TaskManager = TaskManager::new();
+        loaded.read_from_file(&path).unwrap();
+        remove_files(&path);
+        assert_eq!(loaded.projects.active, "work");
+        assert_eq!(names(&loaded.tasks), ["report"]);
+        loaded.switch_project("default").unwrap();
+        assert_eq!(names(&loaded.tasks), ["inbox"]);
+    }
+
+    #[test]
+    fn only_inactive_projects_can_be_deleted()
+    {
+        let mut manager: TaskManager = TaskManager::new();
+        manager.projects.create("old").unwrap();
+        assert!(manager.projects.delete("default").is_err());
+        assert_eq!(manager.projects.delete("old").map(|tasks: Vec<Task>| tasks.len()), Ok(0));
+        assert_eq!(manager.projects.delete("old").err(), Some(TaskError::NoProject("old".to_string())));
+    }
+
+    #[test]
+    fn move_task_relocates_a_task_between_projects()
+    {
+        let mut manager: TaskManager = manager(vec![task("report", Priority::High), task("laundry", Priority::Low)]);
+        manager.projects.create("work").unwrap();
+        manager.move_task("Report", "default", "work").unwrap();
+        assert_eq!(names(&manager.tasks), ["laundry"]);
+
+        manager.switch_project("work").unwrap();
+        assert_eq!(names(&manager.tasks), ["report"]);
+    }
+
+    #[test]
+    fn move_task_fails_for_a_missing_task_or_project()
+    {
+        let mut manager: TaskManager = manager(vec![task("report", Priority::High)]);
+        manager.projects.create("work").unwrap();
+        assert_eq!(manager.move_task("ghost", "default", "work"), Err(TaskError::NotFound("ghost".to_string())));
+        assert_eq!(manager.move_task("report", "default", "hobby"), Err(TaskError::NoProject("hobby".to_string())));
+        assert_eq!(manager.move_task("report", "hobby", "work"), Err(TaskError::NoProject("hobby".to_string())));
+        assert_eq!(names(&manager.tasks), ["report"]);
+    }
+
+    #[test]
+    fn move_task_compares_names_like_the_manager()
+    {
+        let mut manager: TaskManager = TaskManager::new();
+        manager.match_case = true;
+        manager.projects.create("work").unwrap();
+        manager.switch_project("work").unwrap();
+        manager.push(task("API", Priority::Low)).unwrap();
+        manager.switch_project("default").unwrap();
+        manager.push(task("API", Priority::High)).unwrap();
+        manager.push(task("api", Priority::Low)).unwrap();
+
+        manager.move_task("api", "default", "work").unwrap();
+        assert_eq!(names(&manager.tasks), ["API"]);
+        assert_eq!(manager.tasks[0].priority, Priority::High);
+        assert_eq!(manager.move_task("API", "default", "work"), Err(TaskError::Duplicate("API".to_string())));
+        manager.switch_project("work").unwrap();
+        assert_eq!(names(&manager.tasks), ["API", "api"]);
+    }
+
+    #[test]
+    fn parse_due_resolves_relative_dates_against_now()
+    {
+        let now: DateTime<Local> = at(10, 9);
+        let end_of: &dyn Fn(u32) -> DateTime<Local> = &|day: u32| Local.with_ymd_and_hms(2026, 6, day, 23, 59, 59).unwrap();
+        assert_eq!(parse_due("today", now), Ok(end_of(10)));
+        assert_eq!(parse_due(" Tomorrow ", now), Ok(end_of(11)));
+        assert_eq!(parse_due("+3", now), Ok(at(13, 9)));
+        assert_eq!(parse_due("+3d", now), Ok(at(13, 9)));
+        assert_eq!(parse_due("+4h", now), Ok(at(10, 13)));
+        assert_eq!(parse_due("12-06-2026 15:00", now), Ok(at(12, 15)));
+    }
+
+    #[test]
+    fn parse_due_rejects_other_input()
+    {
+        for input in ["", "+", "+xd", "+3w", "next week", "31-02-2026 10:00", "+99999999999999d"]
+        {
+            assert!(matches!(parse_due(input, at(10, 9)), Err(TaskError::Parse(_))), "{}", input);
+        }
+    }
+
+    #[test]
+    fn a_blocked_task_cannot_be_completed()
+    {
+        let mut manager: TaskManager = manager(vec![task("design", Priority::High), task("build", Priority::High)]);
+        manager.set_blocker("build", Some(1)).unwrap();
+
+        assert_eq!(manager.toggle_complete("build"), Err(TaskError::Invalid("Task build is blocked by task design".to_string())));
+        assert!(manager.complete("build").is_err());
+        assert!(!manager.tasks[1].completed);
+        assert!(manager.to_display_string(false, true).contains("🔒 blocked by #1"));
+
+        manager.remove("design").unwrap();
+        assert!(!manager.to_display_string(false, true).contains('🔒'));
+        manager.complete("build").unwrap();
+    }
+
+    #[test]
+    fn a_task_can_be_completed_once_its_blocker_is_done()
+    {
+        let mut manager: TaskManager = manager(vec![task("design", Priority::High), task("build", Priority::High)]);
+        manager.set_blocker("build", Some(1)).unwrap();
+        manager.complete("design").unwrap();
+        assert!(!manager.to_display_string(false, true).contains('🔒'));
+
+        manager.complete("build").unwrap();
+        assert!(manager.tasks[1].completed);
+        assert!(!manager.to_display_string(false, true).contains('🔒'));
+    }
+
+    #[test]
+    fn blockers_cannot_form_a_cycle()
+    {
+        let mut manager: TaskManager = manager(vec![task("a", Priority::Low), task("b", Priority::Low)]);
+        manager.set_blocker("b", Some(1)).unwrap();
+        assert!(manager.set_blocker("a", Some(2)).is_err());
+        assert!(manager.set_blocker("a", Some(1)).is_err());
+        assert_eq!(manager.set_blocker("a", Some(9)), Err(TaskError::NotFound("#9".to_string())));
+    }
+
+    #[test]
+    fn purge_removes_only_completed_tasks_past_the_cutoff()
+    {
+        let finished = |name: &str, day: u32| {
+            let mut task: Task = task(name, Priority::Low);
+            task.completed = true;
+            task.modified_time = at(day, 12);
+            task
+        };
+        let mut open: Task = task("open and old", Priority::Low);
+        open.modified_time = at(1, 12);
+        let mut manager: TaskManager = manager(vec![finished("old", 1), finished("recent", 8), open, finished("ancient", 2)]);
+        manager.clock = Box::new(FixedClock(at(10, 12)));
+
+        assert_eq!(manager.purge_completed_older_than(7), 2);
+        assert_eq!(names(&manager.tasks), ["recent", "open and old"]);
+        assert_eq!(manager.purge_completed_older_than(7), 0);
+        assert_eq!(manager.purge_completed_older_than(i64::MAX), 0);
+    }
+
+    #[test]
+    fn purge_keeps_recent_tasks_of_files_without_a_modified_time()
+    {
+        let path: String = temp_path("purge-old-file.json");
+        let mut done: Task = task("done", Priority::Low);
+        done.completed = true;
+        let mut file: serde_json::Value = serde_json::to_value(manager(vec![done]).to_file()).unwrap();
+        file["tasks"][0].as_object_mut().unwrap().remove("modified_time");
+        std::fs::write(&path, file.to_string()).unwrap();
+        let mut manager: TaskManager = TaskManager::new();
+        manager.read_from_file(&path).unwrap();
+        remove_files(&path);
+
+        manager.clock = Box::new(FixedClock(at(3, 12)));
+        assert_eq!(manager.purge_completed_older_than(7), 0);
+        assert_eq!(manager.purge_completed_older_than(0), 1);
+    }
+
+    #[test]
+    fn compact_lines_cut_long_names_with_an_ellipsis()
+    {
+        let mut long: Task = task(&"x".repeat(COMPACT_NAME_WIDTH + 5), Priority::High);
+        long.due_date = Some(at(2, 9));
+        let line: String = long.to_compact_string();
+        assert_eq!(line, format!("[ ] {}… | High | due 02-06-2026  09:00", "x".repeat(COMPACT_NAME_WIDTH - 1)));
+
+        let mut short: Task = task("short", Priority::Low);
+        short.completed = true;
+        assert_eq!(short.to_compact_string(), format!("[x] {:<width$} | Low", "short", width = COMPACT_NAME_WIDTH));
+    }
+
+    #[test]
+    fn tag_counts_count_each_task_once_per_tag()
+    {
+        let tagged = |name: &str, tags: &str| {
+            let mut task: Task = task(name, Priority::Low);
+            task.tags = Task::parse_tags(tags);
+            task
+        };
+        let manager: TaskManager = manager(vec![
+            tagged("a", "work, urgent"),
+            tagged("b", "Work"),
+            tagged("c", "home, work, WORK"),
+            tagged("d", "")
+        ]);
+        let counts: Vec<(String, usize)> = manager.tag_counts().into_iter().collect();
+        assert_eq!(counts, [("home".to_string(), 1), ("urgent".to_string(), 1), ("work".to_string(), 3)]);
+    }
+
+    #[test]
+    fn timer_accumulates_across_start_and_stop_cycles()
+    {
+        let mut manager: TaskManager = manager(vec![task("write", Priority::Low)]);
+        manager.clock = Box::new(FixedClock(at(2, 9)));
+        assert_eq!(manager.start_timer("write"), Ok(None));
+        manager.clock = Box::new(FixedClock(at(2, 10)));
+        assert_eq!(manager.stop_timer(), Some(("write".to_string(), Duration::hours(1))));
+
+        manager.clock = Box::new(FixedClock(at(3, 9)));
+        manager.start_timer("write").unwrap();
+        manager.clock = Box::new(FixedClock(at(3, 9) + Duration::minutes(25)));
+        manager.stop_timer();
+
+        assert_eq!(manager.tasks[0].time_spent, Duration::minutes(85));
+        assert!(manager.tasks[0].to_display_string(false, at(4, 12), false).contains(" | spent 1h 25m"));
+        assert_eq!(manager.stop_timer(), None);
+    }
+
+    #[test]
+    fn starting_a_timer_stops_the_running_one()
+    {
+        let mut manager: TaskManager = manager(vec![task("write", Priority::Low), task("read", Priority::Low)]);
+        manager.clock = Box::new(FixedClock(at(2, 9)));
+        manager.start_timer("write").unwrap();
+        manager.clock = Box::new(FixedClock(at(2, 11)));
+        assert_eq!(manager.start_timer("read"), Ok(Some(("write".to_string(), Duration::hours(2)))));
+        manager.clock = Box::new(FixedClock(at(2, 12)));
+        manager.stop_timer();
+
+        assert_eq!(manager.tasks[0].time_spent, Duration::hours(2));
+        assert_eq!(manager.tasks[1].time_spent, Duration::hours(1));
+        assert_eq!(manager.start_timer("ghost"), Err(TaskError::NotFound("ghost".to_string())));
+    }
+
+    #[test]
+    fn loading_a_file_drops_the_running_timer()
+    {
+        let path: String = temp_path("timer-load.json");
+        manager(vec![task("other", Priority::Low)]).store_to_file(&path, true).unwrap();
+        let mut manager: TaskManager = manager(vec![task("write", Priority::Low)]);
+        manager.clock = Box::new(FixedClock(at(2, 9)));
+        manager.start_timer("write").unwrap();
+        manager.read_from_file(&path).unwrap();
+        remove_files(&path);
+
+        manager.clock = Box::new(FixedClock(at(2, 11)));
+        assert_eq!(manager.stop_timer(), None);
+        assert_eq!(manager.tasks[0].id, 1);
+        assert_eq!(manager.tasks[0].time_spent, Duration::zero());
+    }
+
+    #[test]
+    fn new_tasks_are_added_at_the_injected_time()
+    {
+        let mut manager: TaskManager = TaskManager::new();
+        manager.clock = Box::new(FixedClock(at(7, 8)));
+        let task: Task = manager.new_task("a".to_string(), String::new(), Priority::Low, None);
+        assert_eq!(task.add_time, at(7, 8));
+        assert_eq!(task.modified_time, at(7, 8));
+
+        let path: String = temp_path("fixed-clock.json");
+        let (mut console, _): (ConsoleForTask, Output) = console(&path, &add_input("b"));
+        console.my_tasks.clock = Box::new(FixedClock(at(7, 9)));
+        run(&mut console);
+        remove_files(&path);
+        assert_eq!(console.my_tasks.tasks[0].add_time, at(7, 9));
+    }
+
+    #[test]
+    fn reschedule_moves_every_overdue_task_to_the_end_of_today()
+    {
+        let due = |name: &str, date: DateTime<Local>, completed: bool| {
+            let mut task: Task = task(name, Priority::Low);
+            task.due_date = Some(date);
+            task.completed = completed;
+            task
+        };
+        let mut manager: TaskManager = manager(vec![
+            due("last week", at(3, 9), false),
+            due("this morning", at(10, 8), false),
+            due("done", at(4, 9), true),
+            due("later", at(12, 9), false)
+        ]);
+        manager.clock = Box::new(FixedClock(at(10, 12)));
+
+        assert_eq!(manager.reschedule_overdue(), 2);
+        let end_of_today: DateTime<Local> = Local.with_ymd_and_hms(2026, 6, 10, 23, 59, 59).unwrap();
+        assert_eq!(manager.tasks[0].due_date, Some(end_of_today));
+        assert_eq!(manager.tasks[1].due_date, Some(end_of_today));
+        assert_eq!(manager.tasks[2].due_date, Some(at(4, 9)));
+        assert_eq!(manager.tasks[3].due_date, Some(at(12, 9)));
+        assert!(manager.overdue().is_empty());
+        assert_eq!(manager.reschedule_overdue(), 0);
+    }
+
+    #[test]
+    fn single_task_export_and_import_round_trip()
+    {
+        let path: String = temp_path("single-task.json");
+        let mut shared: Task = task("share me", Priority::High);
+        shared.tags = vec!["work".to_string()];
+        let mut source: TaskManager = manager(vec![task("blocker", Priority::Low), shared]);
+        source.set_blocker("share me", Some(1)).unwrap();
+        source.export_task("Share Me", &path).unwrap();
+
+        let mut target: TaskManager = manager(vec![task("local", Priority::Low)]);
+        let result: Result<String, TaskError> = target.import_task(&path);
+        remove_files(&path);
+        assert_eq!(result, Ok("share me".to_string()));
+        assert_eq!(names(&target.tasks), ["local", "share me"]);
+        assert_eq!(target.tasks[1].priority, Priority::High);
+        assert_eq!(target.tasks[1].tags, ["work"]);
+        assert_eq!(target.tasks[1].blocked_by, None);
+        assert_ne!(target.tasks[1].id, target.tasks[0].id);
+    }
+
+    #[test]
+    fn exporting_a_missing_task_is_not_found()
+    {
+        let path: String = temp_path("missing-task.json");
+        let manager: TaskManager = manager(vec![task("a", Priority::Low)]);
+        assert_eq!(manager.export_task("ghost", &path), Err(TaskError::NotFound("ghost".to_string())));
+        assert!(!Path::new(&path).exists());
+    }
+
+    #[test]
+    fn recent_returns_the_newest_tasks_first()
+    {
+        let tasks: Vec<Task> = [3, 1, 5, 2, 4]
+            .iter()
+            .map(|day: &u32| Task::with_time(format!("day {}", day), String::new(), Priority::Low, at(*day, 12)))
+            .collect();
+        let manager: TaskManager = manager(tasks);
+        assert_eq!(names(manager.recent(3)), ["day 5", "day 4", "day 3"]);
+        assert_eq!(manager.recent(10).len(), 5);
+        assert!(manager.recent(0).is_empty());
+    }
+
+    #[test]
+    fn count_prints_the_number_of_open_tasks()
+    {
+        let path: String = temp_path("cli-count.json");
+        let mut done: Task = task("done", Priority::High);
+        done.completed = true;
+        manager(vec![task("report", Priority::High), task("call", Priority::Low), done]).store_to_file(&path, true).unwrap();
+        let config: AppConfig = cli_config(&path);
+
+        let mut all: Vec<u8> = Vec::new();
+        assert_eq!(run_cli(args(&["count"]), &config, &mut all, false), 0);
+        let mut high: Vec<u8> = Vec::new();
+        assert_eq!(run_cli(args(&["count", "--priority", "high"]), &config, &mut high, false), 0);
+        let mut very_high: Vec<u8> = Vec::new();
+        assert_eq!(run_cli(args(&["count", "--priority", "4"]), &config, &mut very_high, false), 0);
+        remove_files(&path);
+
+        assert_eq!(String::from_utf8(all).unwrap(), "2\n");
+        assert_eq!(String::from_utf8(high).unwrap(), "1\n");
+        assert_eq!(String::from_utf8(very_high).unwrap(), "0\n");
+    }
+
+    #[test]
+    fn run_cli_writes_listings_and_help_to_the_given_output()
+    {
+        let path: String = temp_path("cli-output.json");
+        manager(vec![task("report", Priority::High)]).store_to_file(&path, true).unwrap();
+        let config: AppConfig = cli_config(&path);
+        let mut plain: Vec<u8> = Vec::new();
+        assert_eq!(run_cli(args(&["list"]), &config, &mut plain, false), 0);
+        let mut colored: Vec<u8> = Vec::new();
+        assert_eq!(run_cli(args(&["list"]), &config, &mut colored, true), 0);
+        let mut help: Vec<u8> = Vec::new();
+        assert_eq!(run_cli(args(&["--help"]), &config, &mut help, false), 0);
+        remove_files(&path);
+
+        let plain: String = String::from_utf8(plain).unwrap();
+        assert!(plain.contains("report"));
+        assert!(!plain.contains('\x1b'));
+        assert!(String::from_utf8(colored).unwrap().contains("\x1b[33mHigh\x1b[0m"));
+        assert!(String::from_utf8(help).unwrap().starts_with("Usage:"));
+    }
+
+    #[test]
+    fn count_fails_for_a_bad_filter()
+    {
+        let path: String = temp_path("cli-count-bad.json");
+        let config: AppConfig = cli_config(&path);
+        let mut out: Vec<u8> = Vec::new();
+        assert_eq!(run_cli(args(&["count", "--priority", "urgent"]), &config, &mut out, false), 1);
+        assert_eq!(run_cli(args(&["count", "--priority"]), &config, &mut out, false), 1);
+        assert_eq!(run_cli(args(&["count", "high"]), &config, &mut out, false), 1);
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn screen_is_cleared_only_when_enabled()
+    {
+        let path: String = temp_path("clear-screen.json");
+        let (mut plain, plain_output): (ConsoleForTask, Output) = console(&path, "5\n");
+        run(&mut plain);
+        let (mut clearing, clearing_output): (ConsoleForTask, Output) = console(&path, "5\n");
+        clearing.clear_screen = true;
+        run(&mut clearing);
+        remove_files(&path);
+
+        assert!(!plain_output.text().contains("\x1b[2J\x1b[H"));
+        let text: String = clearing_output.text();
+        let cleared: usize = text.find("\x1b[2J\x1b[H").unwrap();
+        assert!(text[cleared..].contains("No tasks yet."));
+        assert!(!AppConfig::default().clear_screen);
+    }
 }